@@ -0,0 +1,64 @@
+//! Measures how the compaction threshold of `feed` affects a consumer that reads 1 byte per call
+//! while it keeps peeking ahead, so unread bytes remain in the internal buffer whenever new bytes are read.
+//! Run with `cargo bench --bench compaction`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use std::io::Read;
+use unowned_buf::UnownedReadBuffer;
+
+/// Size of the stream that is read in every iteration.
+const STREAM_SIZE: usize = 16 * 1024 * 1024;
+
+/// Size of the internal buffer.
+const BUFFER_SIZE: usize = 0x4000;
+
+/// Amount of bytes the consumer peeks at before every byte it reads.
+const LOOKAHEAD: usize = 0x400;
+
+/// Returns at most 64 bytes per read, like a slow socket does.
+struct SocketReader<'a>(&'a [u8]);
+
+impl Read for SocketReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = buf.len().min(64).min(self.0.len());
+        buf[..count].copy_from_slice(&self.0[..count]);
+        self.0 = &self.0[count..];
+        Ok(count)
+    }
+}
+
+fn compaction(c: &mut Criterion) {
+    let data: Vec<u8> = (0..STREAM_SIZE).map(|n| n.to_le_bytes()[0]).collect();
+    let mut group = c.benchmark_group("compaction");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(STREAM_SIZE as u64));
+
+    //The default threshold of a quarter of the buffer, a threshold of the buffer size compacts before every read.
+    for (name, threshold) in [("default", None), ("always", Some(BUFFER_SIZE))] {
+        group.bench_function(name, |b| {
+            let mut buf = Box::new(UnownedReadBuffer::<BUFFER_SIZE>::new());
+            if let Some(threshold) = threshold {
+                buf.set_compaction_threshold(threshold);
+            }
+
+            b.iter(|| {
+                let mut read = SocketReader(&data);
+                let mut sum = 0u64;
+                while !buf
+                    .fill_buf_at_least(&mut read, LOOKAHEAD)
+                    .unwrap()
+                    .is_empty()
+                {
+                    sum = sum.wrapping_add(u64::from(buf.read_u8(&mut read).unwrap()));
+                }
+                black_box(sum)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, compaction);
+criterion_main!(benches);
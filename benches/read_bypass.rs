@@ -0,0 +1,61 @@
+//! Measures the large read bypass of `read`, a destination of at least the buffer size skips the internal buffer.
+//! Run with `cargo bench --bench read_bypass`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use std::io::Cursor;
+use unowned_buf::UnownedReadBuffer;
+
+/// Size of the stream that is read in every iteration.
+const STREAM_SIZE: usize = 256 * 1024 * 1024;
+
+/// Size of the destination slice of every read.
+const CHUNK_SIZE: usize = 0x4000;
+
+fn read_bypass(c: &mut Criterion) {
+    let data: Vec<u8> = (0..STREAM_SIZE).map(|n| n.to_le_bytes()[0]).collect();
+    let mut group = c.benchmark_group("read_bypass");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(STREAM_SIZE as u64));
+
+    //The destination is as large as the internal buffer, every read goes directly into it.
+    group.bench_function("bypass", |b| {
+        let mut buf = Box::new(UnownedReadBuffer::<CHUNK_SIZE>::new());
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        b.iter(|| {
+            let mut read = Cursor::new(&data);
+            let mut count = 0usize;
+            loop {
+                let len = buf.read(&mut read, &mut chunk).unwrap();
+                if len == 0 {
+                    break;
+                }
+                count += len;
+            }
+            black_box(count)
+        });
+    });
+
+    //The same destination is smaller than the internal buffer, every byte is copied twice.
+    group.bench_function("through_buffer", |b| {
+        let mut buf = Box::new(UnownedReadBuffer::<{ CHUNK_SIZE * 2 }>::new());
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        b.iter(|| {
+            let mut read = Cursor::new(&data);
+            let mut count = 0usize;
+            loop {
+                let len = buf.read(&mut read, &mut chunk).unwrap();
+                if len == 0 {
+                    break;
+                }
+                count += len;
+            }
+            black_box(count)
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, read_bypass);
+criterion_main!(benches);
@@ -0,0 +1,84 @@
+//! Measures the utf-8 validation of `read_to_string` and `read_line` on a 100 MB ASCII stream.
+//! Run with `cargo bench --bench read_string`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use std::io::{BufRead, BufReader, Read};
+use unowned_buf::UnownedReadBuffer;
+
+/// Size of the stream that is read in every iteration.
+const STREAM_SIZE: usize = 100 * 1024 * 1024;
+
+/// Returns `STREAM_SIZE` bytes of ASCII text lines.
+fn ascii_stream() -> Vec<u8> {
+    let line = b"The quick brown fox jumps over the lazy dog 0123456789\n";
+    line.iter().copied().cycle().take(STREAM_SIZE).collect()
+}
+
+fn read_string(c: &mut Criterion) {
+    let data = ascii_stream();
+    let mut group = c.benchmark_group("read_string");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(STREAM_SIZE as u64));
+
+    group.bench_function("unowned_read_to_string", |b| {
+        let mut buf = Box::new(UnownedReadBuffer::<0x4000>::new());
+        let mut target = String::with_capacity(STREAM_SIZE);
+        b.iter(|| {
+            target.clear();
+            let mut read = data.as_slice();
+            buf.read_to_string(&mut read, &mut target).unwrap();
+            black_box(target.len())
+        });
+    });
+
+    group.bench_function("std_read_to_string", |b| {
+        let mut target = String::with_capacity(STREAM_SIZE);
+        b.iter(|| {
+            target.clear();
+            let mut read = BufReader::with_capacity(0x4000, data.as_slice());
+            read.read_to_string(&mut target).unwrap();
+            black_box(target.len())
+        });
+    });
+
+    group.bench_function("unowned_read_line", |b| {
+        let mut buf = Box::new(UnownedReadBuffer::<0x4000>::new());
+        let mut line = String::new();
+        b.iter(|| {
+            let mut read = data.as_slice();
+            let mut count = 0usize;
+            loop {
+                line.clear();
+                let len = buf.read_line(&mut read, &mut line).unwrap();
+                if len == 0 {
+                    break;
+                }
+                count += len;
+            }
+            black_box(count)
+        });
+    });
+
+    group.bench_function("std_read_line", |b| {
+        let mut line = String::new();
+        b.iter(|| {
+            let mut read = BufReader::with_capacity(0x4000, data.as_slice());
+            let mut count = 0usize;
+            loop {
+                line.clear();
+                let len = read.read_line(&mut line).unwrap();
+                if len == 0 {
+                    break;
+                }
+                count += len;
+            }
+            black_box(count)
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, read_string);
+criterion_main!(benches);
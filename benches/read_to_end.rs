@@ -0,0 +1,57 @@
+//! Measures `read_to_end` on a 256 MiB cursor.
+//! Run with `cargo bench --bench read_to_end`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use std::io::{BufReader, Cursor, Read};
+use unowned_buf::UnownedReadBuffer;
+
+/// Size of the stream that is read in every iteration.
+const STREAM_SIZE: usize = 256 * 1024 * 1024;
+
+fn read_to_end(c: &mut Criterion) {
+    let data: Vec<u8> = (0..STREAM_SIZE).map(|n| n.to_le_bytes()[0]).collect();
+    let mut group = c.benchmark_group("read_to_end");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(STREAM_SIZE as u64));
+
+    //read_to_end hands the remainder to the Read impl once the internal buffer is drained.
+    group.bench_function("unowned_read_to_end", |b| {
+        let mut buf = Box::new(UnownedReadBuffer::<0x4000>::new());
+        let mut target = Vec::with_capacity(STREAM_SIZE);
+        b.iter(|| {
+            target.clear();
+            buf.read_to_end(&mut Cursor::new(&data), &mut target)
+                .unwrap();
+            black_box(target.len())
+        });
+    });
+
+    //read_to_end_counted passes every byte through the internal buffer.
+    group.bench_function("unowned_read_to_end_counted", |b| {
+        let mut buf = Box::new(UnownedReadBuffer::<0x4000>::new());
+        let mut target = Vec::with_capacity(STREAM_SIZE);
+        b.iter(|| {
+            target.clear();
+            buf.read_to_end_counted(&mut Cursor::new(&data), &mut target)
+                .unwrap();
+            black_box(target.len())
+        });
+    });
+
+    group.bench_function("std_read_to_end", |b| {
+        let mut target = Vec::with_capacity(STREAM_SIZE);
+        b.iter(|| {
+            target.clear();
+            BufReader::with_capacity(0x4000, Cursor::new(&data))
+                .read_to_end(&mut target)
+                .unwrap();
+            black_box(target.len())
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, read_to_end);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+//! Compares the ring buffer with the compacting buffer when many bytes stay buffered between reads.
+//! Run with `cargo bench --bench ring`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use std::io::Read;
+use unowned_buf::{UnownedReadBuffer, UnownedRingReadBuffer};
+
+/// Size of the stream that is read in every iteration.
+const STREAM_SIZE: usize = 64 * 1024 * 1024;
+
+/// Size of the internal buffers.
+const BUFFER_SIZE: usize = 0x10000;
+
+/// Amount of bytes that is kept buffered before every record is read.
+const LOOKAHEAD: usize = 0xC000;
+
+/// Returns at most the given amount of bytes per read, like a socket does.
+struct SocketReader<'a>(&'a [u8], usize);
+
+impl Read for SocketReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = buf.len().min(self.1).min(self.0.len());
+        buf[..count].copy_from_slice(&self.0[..count]);
+        self.0 = &self.0[count..];
+        Ok(count)
+    }
+}
+
+fn ring(c: &mut Criterion) {
+    let data: Vec<u8> = (0..STREAM_SIZE).map(|n| n.to_le_bytes()[0]).collect();
+    let mut group = c.benchmark_group("ring");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(STREAM_SIZE as u64));
+
+    //The compacting buffer moves the buffered bytes before every read, smaller reads mean more moves.
+    for chunk in [0x200, 0x1000] {
+        group.bench_function(format!("compacting_{chunk}"), |b| {
+            let mut buf = Box::new(UnownedReadBuffer::<BUFFER_SIZE>::new());
+            b.iter(|| {
+                let mut read = SocketReader(&data, chunk);
+                let mut sum = 0u64;
+                while buf.fill_buf_at_least(&mut read, LOOKAHEAD).unwrap().len() >= 16 {
+                    sum = sum.wrapping_add(buf.read_u128_le(&mut read).unwrap() as u64);
+                }
+                buf.clear();
+                black_box(sum)
+            });
+        });
+
+        group.bench_function(format!("ring_{chunk}"), |b| {
+            let mut buf = Box::new(UnownedRingReadBuffer::<BUFFER_SIZE>::new());
+            b.iter(|| {
+                let mut read = SocketReader(&data, chunk);
+                let mut sum = 0u64;
+                while buf.fill_at_least(&mut read, LOOKAHEAD).unwrap() >= 16 {
+                    sum = sum.wrapping_add(buf.read_u128_le(&mut read).unwrap() as u64);
+                }
+                buf.clear();
+                black_box(sum)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, ring);
+criterion_main!(benches);
@@ -0,0 +1,125 @@
+//! Byte orders and integer types for the `read_int` and `write_int` fns of the buffers.
+
+/// Prevents implementations of the traits of this module outside of this crate.
+mod sealed {
+    /// Implemented for all byte orders and integer types of this module.
+    pub trait Sealed {}
+}
+
+/// Byte order of an encoded integer, either `BigEndian`, `LittleEndian` or `NativeEndian`.
+/// This trait is sealed and cannot be implemented outside of this crate.
+pub trait ByteOrder: sealed::Sealed {
+    /// Decodes an integer from bytes in this byte order.
+    fn decode<I: EndianInt>(bytes: I::Bytes) -> I;
+
+    /// Encodes an integer into bytes in this byte order.
+    fn encode<I: EndianInt>(value: I) -> I::Bytes;
+}
+
+/// Integer type that can be read and written with any `ByteOrder`, implemented for all primitive integers.
+/// This trait is sealed and cannot be implemented outside of this crate.
+pub trait EndianInt: sealed::Sealed + Copy {
+    /// The byte array of the encoded integer.
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default;
+
+    /// Same as the `from_be_bytes` fn of the integer.
+    fn from_be_bytes(bytes: Self::Bytes) -> Self;
+
+    /// Same as the `from_le_bytes` fn of the integer.
+    fn from_le_bytes(bytes: Self::Bytes) -> Self;
+
+    /// Same as the `from_ne_bytes` fn of the integer.
+    fn from_ne_bytes(bytes: Self::Bytes) -> Self;
+
+    /// Same as the `to_be_bytes` fn of the integer.
+    fn to_be_bytes(self) -> Self::Bytes;
+
+    /// Same as the `to_le_bytes` fn of the integer.
+    fn to_le_bytes(self) -> Self::Bytes;
+
+    /// Same as the `to_ne_bytes` fn of the integer.
+    fn to_ne_bytes(self) -> Self::Bytes;
+}
+
+/// Most significant byte first, also known as network byte order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BigEndian;
+
+/// Least significant byte first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LittleEndian;
+
+/// The byte order of the target platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct NativeEndian;
+
+impl sealed::Sealed for BigEndian {}
+impl sealed::Sealed for LittleEndian {}
+impl sealed::Sealed for NativeEndian {}
+
+impl ByteOrder for BigEndian {
+    fn decode<I: EndianInt>(bytes: I::Bytes) -> I {
+        I::from_be_bytes(bytes)
+    }
+
+    fn encode<I: EndianInt>(value: I) -> I::Bytes {
+        value.to_be_bytes()
+    }
+}
+
+impl ByteOrder for LittleEndian {
+    fn decode<I: EndianInt>(bytes: I::Bytes) -> I {
+        I::from_le_bytes(bytes)
+    }
+
+    fn encode<I: EndianInt>(value: I) -> I::Bytes {
+        value.to_le_bytes()
+    }
+}
+
+impl ByteOrder for NativeEndian {
+    fn decode<I: EndianInt>(bytes: I::Bytes) -> I {
+        I::from_ne_bytes(bytes)
+    }
+
+    fn encode<I: EndianInt>(value: I) -> I::Bytes {
+        value.to_ne_bytes()
+    }
+}
+
+/// Implements `EndianInt` for the given integer types.
+macro_rules! endian_int {
+    ($($ty:ty),*) => {$(
+        impl sealed::Sealed for $ty {}
+
+        impl EndianInt for $ty {
+            type Bytes = [u8; core::mem::size_of::<$ty>()];
+
+            fn from_be_bytes(bytes: Self::Bytes) -> Self {
+                Self::from_be_bytes(bytes)
+            }
+
+            fn from_le_bytes(bytes: Self::Bytes) -> Self {
+                Self::from_le_bytes(bytes)
+            }
+
+            fn from_ne_bytes(bytes: Self::Bytes) -> Self {
+                Self::from_ne_bytes(bytes)
+            }
+
+            fn to_be_bytes(self) -> Self::Bytes {
+                Self::to_be_bytes(self)
+            }
+
+            fn to_le_bytes(self) -> Self::Bytes {
+                Self::to_le_bytes(self)
+            }
+
+            fn to_ne_bytes(self) -> Self::Bytes {
+                Self::to_ne_bytes(self)
+            }
+        }
+    )*};
+}
+
+endian_int!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
@@ -0,0 +1,238 @@
+//! Extension trait that groups the helpers for reading binary and text encoded values.
+
+use crate::io;
+use crate::io::Read;
+use crate::ReadBuffer;
+use crate::{for_each_int, ByteOrder, EndianInt, NulReadOutcome};
+use alloc::ffi::CString;
+use alloc::vec::Vec;
+
+/// Declares the fixed size integer read fns of `UnownedReadExt`.
+macro_rules! ext_int_fns {
+    ($($name:ident, $ty:ty, $decode:ident, $desc:literal;)*) => {$(
+        #[doc = concat!("Reads ", $desc, ".")]
+        #[doc = ""]
+        #[doc = "# Errors"]
+        #[doc = "Propagated from the `Read` impl"]
+        #[doc = "`ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered."]
+        fn $name<T: Read>(&mut self, read: &mut T) -> io::Result<$ty>;
+    )*};
+}
+
+/// Implements the fixed size integer read fns of `UnownedReadExt` by delegating to the inherent fns.
+macro_rules! ext_int_delegates {
+    ($($name:ident, $ty:ty, $decode:ident, $desc:literal;)*) => {$(
+        fn $name<T: Read>(&mut self, read: &mut T) -> io::Result<$ty> {
+            Self::$name(self, read)
+        }
+    )*};
+}
+
+/// Helpers for reading arrays, integers, floats, varints, ASCII numbers and NUL terminated strings from a read buffer.
+///
+/// All fns delegate to the inherent fns of `UnownedReadBuffer` with the same name, see those for details.
+/// Import this trait to use the helpers in generic code or to build your own helpers on top of them.
+pub trait UnownedReadExt {
+    /// Reads exactly N bytes and returns them as an array.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before N bytes were read.
+    ///
+    fn read_array<const N: usize, T: Read>(&mut self, read: &mut T) -> io::Result<[u8; N]>;
+
+    for_each_int!(ext_int_fns);
+
+    /// Reads an integer in the given byte order, for example `read_int::<u32, BigEndian>(read)`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    fn read_int<I: EndianInt, O: ByteOrder>(&mut self, read: &mut impl Read) -> io::Result<I>;
+
+    /// Reads big endian IEEE 754 bits as f32.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    fn read_f32_be<T: Read>(&mut self, read: &mut T) -> io::Result<f32>;
+
+    /// Reads little endian IEEE 754 bits as f32.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    fn read_f32_le<T: Read>(&mut self, read: &mut T) -> io::Result<f32>;
+
+    /// Reads big endian IEEE 754 bits as f64.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    fn read_f64_be<T: Read>(&mut self, read: &mut T) -> io::Result<f64>;
+
+    /// Reads little endian IEEE 754 bits as f64.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    fn read_f64_le<T: Read>(&mut self, read: &mut T) -> io::Result<f64>;
+
+    /// Reads an unsigned LEB128 varint.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 10 bytes or does not fit into an u64.
+    ///
+    fn read_varint_u64<T: Read>(&mut self, read: &mut T) -> io::Result<u64>;
+
+    /// Reads an unsigned LEB128 varint of at most 5 bytes.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 5 bytes or does not fit into an u32.
+    ///
+    fn read_varint_u32<T: Read>(&mut self, read: &mut T) -> io::Result<u32>;
+
+    /// Reads a zigzag encoded signed LEB128 varint.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 10 bytes or does not fit into an i64.
+    ///
+    fn read_varint_i64<T: Read>(&mut self, read: &mut T) -> io::Result<i64>;
+
+    /// Reads a zigzag encoded signed LEB128 varint of at most 5 bytes.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 5 bytes or does not fit into an i32.
+    ///
+    fn read_varint_i32<T: Read>(&mut self, read: &mut T) -> io::Result<i32>;
+
+    /// Reads an ASCII decimal number such as "  +1234".
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if EOF occurred before anything but whitespace was read.
+    /// `ErrorKind::InvalidData` if there are no digits, the number is negative or does not fit into u64.
+    ///
+    fn read_ascii_u64<T: Read>(&mut self, read: &mut T) -> io::Result<u64>;
+
+    /// Reads an ASCII decimal number such as "  -1234".
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if EOF occurred before anything but whitespace was read.
+    /// `ErrorKind::InvalidData` if there are no digits or the number does not fit into i64.
+    ///
+    fn read_ascii_i64<T: Read>(&mut self, read: &mut T) -> io::Result<i64>;
+
+    /// Reads until a NUL (0x00) byte is found. The NUL byte is consumed but not appended to buf.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the NUL byte was found.
+    ///
+    fn read_cstr<T: Read>(&mut self, read: &mut T, buf: &mut Vec<u8>) -> io::Result<usize>;
+
+    /// Reads a NUL terminated string of at most limit bytes, excluding the NUL byte.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if EOF occurred before the NUL byte was found.
+    /// `ErrorKind::InvalidData` if no NUL byte was found within limit bytes.
+    ///
+    fn read_cstring<T: Read>(&mut self, read: &mut T, limit: usize) -> io::Result<CString>;
+
+    /// Reads until a NUL (0x00) byte is found, EOF occurred or limit bytes have been appended to buf.
+    /// The NUL byte is consumed but not appended to buf and does not count towards the limit.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    fn read_nul_terminated<T: Read>(
+        &mut self,
+        read: &mut T,
+        limit: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<NulReadOutcome>;
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> UnownedReadExt for ReadBuffer<B> {
+    fn read_array<const N: usize, T: Read>(&mut self, read: &mut T) -> io::Result<[u8; N]> {
+        Self::read_array(self, read)
+    }
+
+    for_each_int!(ext_int_delegates);
+
+    fn read_int<I: EndianInt, O: ByteOrder>(&mut self, read: &mut impl Read) -> io::Result<I> {
+        Self::read_int::<I, O>(self, read)
+    }
+
+    fn read_f32_be<T: Read>(&mut self, read: &mut T) -> io::Result<f32> {
+        Self::read_f32_be(self, read)
+    }
+
+    fn read_f32_le<T: Read>(&mut self, read: &mut T) -> io::Result<f32> {
+        Self::read_f32_le(self, read)
+    }
+
+    fn read_f64_be<T: Read>(&mut self, read: &mut T) -> io::Result<f64> {
+        Self::read_f64_be(self, read)
+    }
+
+    fn read_f64_le<T: Read>(&mut self, read: &mut T) -> io::Result<f64> {
+        Self::read_f64_le(self, read)
+    }
+
+    fn read_varint_u64<T: Read>(&mut self, read: &mut T) -> io::Result<u64> {
+        Self::read_varint_u64(self, read)
+    }
+
+    fn read_varint_u32<T: Read>(&mut self, read: &mut T) -> io::Result<u32> {
+        Self::read_varint_u32(self, read)
+    }
+
+    fn read_varint_i64<T: Read>(&mut self, read: &mut T) -> io::Result<i64> {
+        Self::read_varint_i64(self, read)
+    }
+
+    fn read_varint_i32<T: Read>(&mut self, read: &mut T) -> io::Result<i32> {
+        Self::read_varint_i32(self, read)
+    }
+
+    fn read_ascii_u64<T: Read>(&mut self, read: &mut T) -> io::Result<u64> {
+        Self::read_ascii_u64(self, read)
+    }
+
+    fn read_ascii_i64<T: Read>(&mut self, read: &mut T) -> io::Result<i64> {
+        Self::read_ascii_i64(self, read)
+    }
+
+    fn read_cstr<T: Read>(&mut self, read: &mut T, buf: &mut Vec<u8>) -> io::Result<usize> {
+        Self::read_cstr(self, read, buf)
+    }
+
+    fn read_cstring<T: Read>(&mut self, read: &mut T, limit: usize) -> io::Result<CString> {
+        Self::read_cstring(self, read, limit)
+    }
+
+    fn read_nul_terminated<T: Read>(
+        &mut self,
+        read: &mut T,
+        limit: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<NulReadOutcome> {
+        Self::read_nul_terminated(self, read, limit, buf)
+    }
+}
@@ -0,0 +1,145 @@
+//! Buffer types that are generic over their storage.
+//!
+//! This module is private, so the storage generic never becomes part of the public API.
+//! The crate root only exposes these types through type aliases that are generic over the size of the buffer.
+
+use crate::io;
+use crate::io::{Read, Write};
+use crate::{Chain, ObservingWrite};
+
+/// Storage generic type behind the `UnownedWriteBuffer` and `DynWriteBuffer` type aliases.
+#[derive(Debug, Clone)]
+pub struct WriteBuffer<B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// How many bytes in the buffer have we filled and must still be sent to a `Write` impl?
+    pub(crate) fill_count: usize,
+    /// How often was the `Write` impl called?
+    pub(crate) write_calls: u64,
+    /// The buffer
+    pub(crate) buffer: B,
+}
+
+/// Storage generic type behind the `UnownedReadBuffer` and `DynReadBuffer` type aliases.
+#[derive(Debug, Clone)]
+pub struct ReadBuffer<B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// How much have we read?
+    pub(crate) read_count: usize,
+    /// How much can we read?
+    pub(crate) fill_count: usize,
+    /// Incremented whenever already read bytes are moved or may be overwritten, this invalidates all marks.
+    pub(crate) generation: u64,
+    /// Compact before reading only if fewer bytes are free at the end, 0 means always compact.
+    pub(crate) compaction_threshold: usize,
+    /// How often was the `Read` impl called?
+    pub(crate) read_calls: u64,
+    /// The buffer
+    pub(crate) buffer: B,
+}
+
+/// Storage generic type behind the `BorrowedWriteBuffer` and `BorrowedDynWriteBuffer` type aliases.
+pub struct BorrowedWriteBuffer<'a, T: Write, B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut WriteBuffer<B>,
+    /// Write ref
+    pub(crate) write: &'a mut T,
+}
+
+/// Storage generic type behind the `ObservedWriteBuffer` type alias.
+pub struct ObservedWriteBuffer<'a, T: Write, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut WriteBuffer<B>,
+    /// the observed write impl
+    pub(crate) write: ObservingWrite<'a, T, F>,
+}
+
+/// Storage generic type behind the `BorrowedReadBuffer` and `BorrowedDynReadBuffer` type aliases.
+pub struct BorrowedReadBuffer<'a, T: Read, B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut ReadBuffer<B>,
+    /// read ref
+    pub(crate) read: &'a mut T,
+}
+
+/// Storage generic type behind the `LimitedRead` type alias.
+pub struct LimitedRead<'a, T: Read, B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut ReadBuffer<B>,
+    /// read ref
+    pub(crate) read: &'a mut T,
+    /// How many bytes may still be read
+    pub(crate) limit: u64,
+}
+
+/// Storage generic type behind the `Bytes` type alias.
+pub struct Bytes<'a, T: Read, B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut ReadBuffer<B>,
+    /// read ref
+    pub(crate) read: &'a mut T,
+}
+
+/// Storage generic type behind the `ObservedReadBuffer` type alias.
+pub struct ObservedReadBuffer<'a, T: Read, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut ReadBuffer<B>,
+    /// read ref
+    pub(crate) read: &'a mut T,
+    /// the observer
+    pub(crate) observer: F,
+}
+
+/// Storage generic type behind the `TeeReadBuffer` type alias.
+pub struct TeeReadBuffer<'a, T: Read, W: Write, B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut ReadBuffer<B>,
+    /// read ref
+    pub(crate) read: &'a mut T,
+    /// sink ref
+    pub(crate) sink: &'a mut W,
+    /// Should errors of the sink be kept out of the read path?
+    pub(crate) ignore_sink_errors: bool,
+    /// Error of the sink that was not yet reported
+    pub(crate) sink_error: Option<io::Error>,
+}
+
+/// Storage generic type behind the `ChainedReadBuffer` type alias.
+pub struct ChainedReadBuffer<'a, T: Read, U: Read, B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut ReadBuffer<B>,
+    /// the chained read impls
+    pub(crate) read: Chain<'a, T, U>,
+}
+
+/// Storage generic type behind the `Chars` type alias.
+pub struct Chars<'a, T: Read, B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut ReadBuffer<B>,
+    /// read ref
+    pub(crate) read: &'a mut T,
+}
+
+/// Storage generic type behind the `Records` type alias.
+pub struct Records<'a, T: Read, B: AsRef<[u8]> + AsMut<[u8]>, const N: usize> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut ReadBuffer<B>,
+    /// read ref
+    pub(crate) read: &'a mut T,
+}
+
+/// Storage generic type behind the `UnownedRingReadBuffer` type alias.
+#[derive(Debug, Clone)]
+pub struct RingReadBuffer<B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// Index of the next unread byte
+    pub(crate) head: usize,
+    /// How many unread bytes are there?
+    pub(crate) len: usize,
+    /// The buffer
+    pub(crate) buffer: B,
+}
+
+/// Storage generic type behind the `BorrowedRingReadBuffer` type alias.
+pub struct BorrowedRingReadBuffer<'a, T: Read, B: AsRef<[u8]> + AsMut<[u8]>> {
+    /// buffer ref
+    pub(crate) buffer: &'a mut RingReadBuffer<B>,
+    /// read ref
+    pub(crate) read: &'a mut T,
+}
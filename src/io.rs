@@ -0,0 +1,221 @@
+//! The io types used by this crate.
+//!
+//! With the `std` feature (enabled by default) these are just the types from `std::io`.
+//! Without it, this module provides minimal replacements that only depend on `core` and `alloc`
+//! so that the buffers can be used on targets without `std`.
+//! Implement `Read`/`Write` from this module for your peripherals in that case.
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Read, Result, Write};
+
+#[cfg(not(feature = "std"))]
+pub use no_std::{Error, ErrorKind, Read, Result, Write};
+
+/// Minimal replacements for the `std::io` types.
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::fmt::{Display, Formatter};
+
+    /// Result type of all io operations.
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// The kind of error, equivalent to the variants of `std::io::ErrorKind` with the same name.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
+    pub enum ErrorKind {
+        /// The data is not valid, for example it is not valid utf-8.
+        InvalidData,
+        /// A parameter was not valid.
+        InvalidInput,
+        /// EOF occurred before the operation could be completed.
+        UnexpectedEof,
+        /// The operation would have to block to complete.
+        WouldBlock,
+        /// The operation was interrupted and can be retried.
+        Interrupted,
+        /// The operation timed out.
+        TimedOut,
+        /// A write returned Ok(0).
+        WriteZero,
+        /// Any other error.
+        Other,
+    }
+
+    impl ErrorKind {
+        /// Returns a description of the error kind.
+        const fn as_str(self) -> &'static str {
+            match self {
+                Self::InvalidData => "invalid data",
+                Self::InvalidInput => "invalid input parameter",
+                Self::UnexpectedEof => "unexpected end of file",
+                Self::WouldBlock => "operation would block",
+                Self::Interrupted => "operation interrupted",
+                Self::TimedOut => "timed out",
+                Self::WriteZero => "write zero",
+                Self::Other => "other error",
+            }
+        }
+    }
+
+    /// Error of an io operation, consisting of an `ErrorKind` and an optional static message.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Error {
+        /// The kind
+        kind: ErrorKind,
+        /// The message
+        message: Option<&'static str>,
+    }
+
+    impl Error {
+        /// Creates a new error with the given kind and message.
+        #[must_use]
+        pub const fn new(kind: ErrorKind, message: &'static str) -> Self {
+            Self {
+                kind,
+                message: Some(message),
+            }
+        }
+
+        /// Returns the kind of the error.
+        #[must_use]
+        pub const fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl From<ErrorKind> for Error {
+        fn from(kind: ErrorKind) -> Self {
+            Self {
+                kind,
+                message: None,
+            }
+        }
+    }
+
+    impl Display for Error {
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            f.write_str(self.message.unwrap_or_else(|| self.kind.as_str()))
+        }
+    }
+
+    impl core::error::Error for Error {}
+
+    /// Minimal equivalent of `std::io::Read`.
+    pub trait Read {
+        /// Reads some bytes into buf and returns how many bytes were read.
+        /// Ok(0) means EOF unless buf is empty.
+        ///
+        /// # Errors
+        /// Implementation specific
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+        /// Reads exactly enough bytes to fill buf. `ErrorKind::Interrupted` is retried.
+        ///
+        /// # Errors
+        /// Propagated from `read`
+        /// `ErrorKind::UnexpectedEof` if `read` returns Ok(0) before buf was filled.
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+            let mut buf = buf;
+            while !buf.is_empty() {
+                match self.read(buf) {
+                    Ok(0) => return Err(Error::from(ErrorKind::UnexpectedEof)),
+                    Ok(count) => buf = &mut buf[count..],
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Reads all bytes until EOF and appends them to buf. `ErrorKind::Interrupted` is retried.
+        ///
+        /// # Errors
+        /// Propagated from `read`
+        fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+            let mut chunk = [0u8; 64];
+            let mut count = 0usize;
+            loop {
+                match self.read(&mut chunk) {
+                    Ok(0) => return Ok(count),
+                    Ok(read) => {
+                        buf.extend_from_slice(&chunk[..read]);
+                        count += read;
+                    }
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        /// Reads all bytes until EOF and appends them to buf.
+        ///
+        /// # Errors
+        /// Propagated from `read`
+        /// `ErrorKind::InvalidData` if the bytes are not valid utf-8, nothing is appended to buf in this case.
+        fn read_to_string(&mut self, buf: &mut String) -> Result<usize> {
+            let mut bytes = Vec::new();
+            let count = self.read_to_end(&mut bytes)?;
+            buf.push_str(&String::from_utf8(bytes).map_err(|_| {
+                Error::new(ErrorKind::InvalidData, "stream did not contain valid utf-8")
+            })?);
+            Ok(count)
+        }
+    }
+
+    /// Minimal equivalent of `std::io::Write`.
+    pub trait Write {
+        /// Writes some bytes from buf and returns how many bytes were written.
+        ///
+        /// # Errors
+        /// Implementation specific
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        /// Flushes any bytes buffered by the implementation.
+        ///
+        /// # Errors
+        /// Implementation specific
+        fn flush(&mut self) -> Result<()>;
+
+        /// Writes all bytes from buf. `ErrorKind::Interrupted` is retried.
+        ///
+        /// # Errors
+        /// Propagated from `write`
+        /// `ErrorKind::WriteZero` if `write` returns Ok(0) before all bytes were written.
+        fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+            let mut buf = buf;
+            while !buf.is_empty() {
+                match self.write(buf) {
+                    Ok(0) => return Err(Error::from(ErrorKind::WriteZero)),
+                    Ok(count) => buf = &buf[count..],
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    impl Read for &[u8] {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let count = buf.len().min(self.len());
+            buf[..count].copy_from_slice(&self[..count]);
+            *self = &self[count..];
+            Ok(count)
+        }
+    }
+
+    impl Write for Vec<u8> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+}
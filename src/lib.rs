@@ -47,6 +47,7 @@
 //! }
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(clippy::correctness, unsafe_code)]
 #![warn(
     clippy::perf,
@@ -64,63 +65,265 @@
     clippy::used_underscore_binding
 )]
 
-use std::fmt::{Debug, Formatter};
-use std::io;
-use std::io::{BufRead, ErrorKind, Read, Write};
+extern crate alloc;
+
+mod endian;
+mod ext;
+mod generic;
+pub mod io;
+mod ring;
+
+pub use crate::endian::{BigEndian, ByteOrder, EndianInt, LittleEndian, NativeEndian};
+pub use crate::ext::UnownedReadExt;
+use crate::generic::{ReadBuffer, WriteBuffer};
+use crate::io::{ErrorKind, Read, Write};
+pub use crate::ring::{BorrowedRingReadBuffer, UnownedRingReadBuffer};
+use alloc::boxed::Box;
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter};
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::io::{BufRead, IoSliceMut};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// Invokes the given macro with the name, type, decoding fn and description of every fixed size integer read fn.
+macro_rules! for_each_int {
+    ($macro:ident) => {
+        $macro! {
+            read_u8, u8, from_be_bytes, "an u8";
+            read_i8, i8, from_be_bytes, "an i8";
+            read_u16_be, u16, from_be_bytes, "a big endian u16";
+            read_u16_le, u16, from_le_bytes, "a little endian u16";
+            read_i16_be, i16, from_be_bytes, "a big endian i16";
+            read_i16_le, i16, from_le_bytes, "a little endian i16";
+            read_u32_be, u32, from_be_bytes, "a big endian u32";
+            read_u32_le, u32, from_le_bytes, "a little endian u32";
+            read_i32_be, i32, from_be_bytes, "a big endian i32";
+            read_i32_le, i32, from_le_bytes, "a little endian i32";
+            read_u64_be, u64, from_be_bytes, "a big endian u64";
+            read_u64_le, u64, from_le_bytes, "a little endian u64";
+            read_i64_be, i64, from_be_bytes, "a big endian i64";
+            read_i64_le, i64, from_le_bytes, "a little endian i64";
+            read_u128_be, u128, from_be_bytes, "a big endian u128";
+            read_u128_le, u128, from_le_bytes, "a little endian u128";
+            read_i128_be, i128, from_be_bytes, "a big endian i128";
+            read_i128_le, i128, from_le_bytes, "a little endian i128";
+        }
+    };
+}
+
+/// Generates the fixed size integer read fns of `ReadBuffer` and `RingReadBuffer`.
+macro_rules! read_int_fns {
+    ($($name:ident, $ty:ty, $decode:ident, $desc:literal;)*) => {$(
+        #[doc = concat!("Reads ", $desc, ".")]
+        #[doc = "The bytes are only consumed if all of them could be read, on error they remain in the internal buffer."]
+        #[doc = ""]
+        #[doc = "# Errors"]
+        #[doc = "Propagated from the `Read` impl"]
+        #[doc = "`ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered."]
+        pub fn $name<T: Read>(&mut self, read: &mut T) -> io::Result<$ty> {
+            Ok(<$ty>::$decode(self.read_int_bytes(read)?))
+        }
+    )*};
+}
+
+//The ext and ring modules are declared before the macros above, so they import them by path.
+pub(crate) use {for_each_int, read_int_fns};
+
+/// Generates the fixed size integer read fns of `BorrowedReadBuffer`.
+macro_rules! borrowed_read_int_fns {
+    ($($name:ident, $ty:ty, $decode:ident, $desc:literal;)*) => {$(
+        #[doc = concat!("Reads ", $desc, ". See `UnownedReadBuffer::", stringify!($name), "`.")]
+        #[doc = ""]
+        #[doc = "# Errors"]
+        #[doc = "Propagated from the `Read` impl"]
+        #[doc = "`ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered."]
+        pub fn $name(&mut self) -> io::Result<$ty> {
+            self.buffer.$name(self.read)
+        }
+    )*};
+}
 
 ///
-/// Unowned Write buffer.
+/// Unowned Write buffer backed by an array.
 ///
 /// # S Generic: Size of the buffer.
 /// beware that if this size is too large, and you stack allocate this struct
 /// then you will hit the guard page and your program will crash.
-/// If you must use very large buffers then Box this struct.
+/// If you must use very large buffers then use a `DynWriteBuffer` or `UnownedWriteBuffer::new_boxed`.
 ///
 ///
-#[derive(Debug)]
-pub struct UnownedWriteBuffer<const S: usize> {
-    /// How many bytes in the buffer have we filled and must still be sent to a `Write` impl?
-    fill_count: usize,
-    /// The buffer
-    buffer: [u8; S],
-}
+pub type UnownedWriteBuffer<const S: usize> = WriteBuffer<[u8; S]>;
+
+///
+/// Unowned Write buffer backed by a heap allocated slice.
+/// The size of the buffer is chosen at runtime by calling `DynWriteBuffer::with_capacity`.
+///
+pub type DynWriteBuffer = WriteBuffer<Box<[u8]>>;
 
-impl<const S: usize> UnownedWriteBuffer<S> {
+impl<const S: usize> WriteBuffer<[u8; S]> {
     /// Construct a new Buffer
-    /// # Panics
+    ///
+    /// # Compile errors
     /// if S is smaller than 16.
+    /// ```compile_fail
+    /// let _ = unowned_buf::UnownedWriteBuffer::<8>::new();
+    /// ```
     #[must_use]
     pub const fn new() -> Self {
-        let buf = Self {
+        const { assert!(S >= 16, "UnownedWriteBuffer is too small") };
+
+        Self {
             fill_count: 0,
+            write_calls: 0,
             buffer: [0; S],
-        };
+        }
+    }
+
+    /// Construct a new `DynWriteBuffer` of size S, the buffer is allocated directly on the heap.
+    /// Unlike `Box::new(UnownedWriteBuffer::new())` the buffer is never placed on the stack,
+    /// this is the recommended way to construct buffers larger than a few KiB.
+    ///
+    /// # Compile errors
+    /// if S is smaller than 16.
+    #[must_use]
+    pub fn new_boxed() -> DynWriteBuffer {
+        const { assert!(S >= 16, "UnownedWriteBuffer is too small") };
+
+        DynWriteBuffer::with_capacity(S)
+    }
+
+    /// Returns the size of the buffer.
+    #[must_use]
+    #[allow(clippy::unused_self)] //Part of the public API through the type alias.
+    pub const fn size(&self) -> usize {
+        S
+    }
+
+    /// Decomposes this buffer into the backing array and the fill count.
+    /// The unflushed bytes are `array[..fill_count]`.
+    /// With the `zeroize` feature the internal buffer is still zeroized, the returned copy is not.
+    #[must_use]
+    #[cfg_attr(not(feature = "zeroize"), allow(clippy::missing_const_for_fn))]
+    pub fn into_parts(self) -> ([u8; S], usize) {
+        (self.buffer, self.fill_count)
+    }
 
-        assert!(buf.buffer.len() >= 16, "UnownedWriteBuffer is too small");
+    /// Constructs a buffer from parts previously returned by `into_parts`.
+    ///
+    /// # Panics
+    /// if fill count is larger than S.
+    ///
+    /// # Compile errors
+    /// if S is smaller than 16.
+    #[must_use]
+    pub const fn from_parts(buffer: [u8; S], fill_count: usize) -> Self {
+        const { assert!(S >= 16, "UnownedWriteBuffer is too small") };
+        assert!(fill_count <= S, "fill_count is larger than the buffer");
 
-        buf
+        Self {
+            fill_count,
+            write_calls: 0,
+            buffer,
+        }
     }
 }
 
-impl Default for UnownedWriteBuffer<0x4000> {
-    fn default() -> Self {
+impl WriteBuffer<Box<[u8]>> {
+    /// Construct a new heap allocated Buffer of the given size.
+    /// # Panics
+    /// if size is smaller than 16.
+    #[must_use]
+    pub fn with_capacity(size: usize) -> Self {
+        assert!(size >= 16, "DynWriteBuffer is too small");
         Self {
             fill_count: 0,
-            buffer: [0; 0x4000],
+            write_calls: 0,
+            buffer: vec![0; size].into_boxed_slice(),
+        }
+    }
+
+    /// Returns the size of the buffer.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Decomposes this buffer into the backing slice and the fill count.
+    /// The unflushed bytes are `slice[..fill_count]`.
+    #[must_use]
+    pub fn into_parts(mut self) -> (Box<[u8]>, usize) {
+        (core::mem::take(&mut self.buffer), self.fill_count)
+    }
+
+    /// Constructs a buffer from parts previously returned by `into_parts`.
+    ///
+    /// # Panics
+    /// if the buffer is smaller than 16 or fill count is larger than the buffer.
+    #[must_use]
+    pub fn from_parts(buffer: Box<[u8]>, fill_count: usize) -> Self {
+        assert!(buffer.len() >= 16, "DynWriteBuffer is too small");
+        assert!(
+            fill_count <= buffer.len(),
+            "fill_count is larger than the buffer"
+        );
+
+        Self {
+            fill_count,
+            write_calls: 0,
+            buffer,
         }
     }
 }
 
-impl<const S: usize> UnownedWriteBuffer<S> {
+impl<B: AsRef<[u8]> + AsMut<[u8]>> WriteBuffer<B> {
     /// Returns the amount of bytes that can still be written into the internal buffer.
     #[must_use]
-    pub const fn available(&self) -> usize {
-        self.buffer.len() - self.fill_count
+    pub fn available(&self) -> usize {
+        self.buffer.as_ref().len() - self.fill_count
     }
 
+    /// Returns the amount of bytes that can still be written into the internal buffer, same as `available()`.
     #[must_use]
-    pub const fn size(&self) -> usize {
-        S
+    pub fn remaining_capacity(&self) -> usize {
+        self.available()
+    }
+
+    /// Returns true if the internal buffer contains no bytes that still have to be flushed.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.fill_count == 0
+    }
+
+    /// Returns true if no more bytes can be written into the internal buffer without flushing it.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.available() == 0
+    }
+
+    /// Returns the amount of bytes in the internal buffer that still have to be flushed.
+    #[must_use]
+    pub const fn flushable(&self) -> usize {
+        self.fill_count
+    }
+
+    /// Discards all but the first len bytes that still have to be flushed, does nothing if fewer bytes are buffered.
+    /// Remember `flushable()` before a speculative write to roll it back with this fn.
+    /// Bytes that were already flushed to the `Write` impl cannot be rolled back.
+    pub fn truncate(&mut self, len: usize) {
+        self.fill_count = self.fill_count.min(len);
+    }
+
+    /// Returns how often this buffer called `Write::write` of a `Write` impl.
+    /// Handing a large write to `Write::write_all` of the `Write` impl counts as a single call, `Write::flush` is not counted.
+    /// Useful to verify that the size of the internal buffer is large enough to save calls.
+    #[must_use]
+    pub const fn underlying_write_calls(&self) -> u64 {
+        self.write_calls
     }
 
     /// Push some bytes to the Write impl.
@@ -131,16 +334,16 @@ impl<const S: usize> UnownedWriteBuffer<S> {
 
         let mut count = 0usize;
         while count < self.fill_count {
-            match write.write(&self.buffer[count..self.fill_count]) {
+            self.write_calls += 1;
+            match write.write(&self.buffer.as_ref()[count..self.fill_count]) {
                 Ok(cnt) => {
                     count += cnt;
-                    continue;
                 }
                 Err(e) => {
                     if count == 0 {
                         return Err(e);
                     }
-                    self.buffer.copy_within(count..self.fill_count, 0);
+                    self.buffer.as_mut().copy_within(count..self.fill_count, 0);
                     self.fill_count -= count;
                     return Err(e);
                 }
@@ -159,11 +362,64 @@ impl<const S: usize> UnownedWriteBuffer<S> {
         write.flush()
     }
 
+    /// Ensures that at least n bytes can be written to the internal buffer without calling the Write impl.
+    /// If fewer than n bytes are free then all bytes of the internal buffer are written to the Write impl,
+    /// `Write::flush` is not called. Returns the amount of free bytes afterward,
+    /// which is the size of the internal buffer if n is larger than that.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn ensure_writable<T: Write>(&mut self, write: &mut T, n: usize) -> io::Result<usize> {
+        if self.available() < n {
+            self.push(write)?;
+        }
+
+        Ok(self.available())
+    }
+
+    /// Same as `flush` but returns the amount of bytes that were pushed to the Write impl before `Write::flush` was called.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn flush_counted<T: Write>(&mut self, write: &mut T) -> io::Result<usize> {
+        let count = self.fill_count;
+        self.flush(write)?;
+        Ok(count)
+    }
+
+    /// Performs a single call to `Write::write` with the bytes of the internal buffer
+    /// and returns how many bytes were written. The remaining bytes are moved to the start of the internal buffer.
+    /// Unlike `flush` this does not loop until the internal buffer is empty and does not call `Write::flush`.
+    /// This is intended for non-blocking writers. Returns Ok(0) without calling the Write impl if the internal buffer is empty.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl, including `WouldBlock`. The internal buffer is unchanged in this case.
+    /// `ErrorKind::WriteZero` if the Write impl returned Ok(0).
+    ///
+    pub fn try_flush<T: Write>(&mut self, write: &mut T) -> io::Result<usize> {
+        if self.fill_count == 0 {
+            return Ok(0);
+        }
+
+        self.write_calls += 1;
+        let count = write.write(&self.buffer.as_ref()[..self.fill_count])?;
+        if count == 0 {
+            return Err(io::Error::from(ErrorKind::WriteZero));
+        }
+
+        self.buffer.as_mut().copy_within(count..self.fill_count, 0);
+        self.fill_count -= count;
+        Ok(count)
+    }
+
     /// Write as many bytes as can still fit to the internal buffer.
     /// This function returns 0 if the internal buffer is full.
     /// If the supplied buffer is only partially written then this fn guarantees that
     /// the entire internal buffer has been filled and subsequent calls to `try_write` are pointless
     /// unless flush or `write`/`write_all` are first called.
+    #[allow(clippy::extra_unused_type_parameters)] //Part of the public API through the type alias.
     pub fn try_write<T: Write>(&mut self, buffer: &[u8]) -> usize {
         if buffer.is_empty() {
             return 0;
@@ -175,13 +431,14 @@ impl<const S: usize> UnownedWriteBuffer<S> {
 
         if available < buffer.len() {
             //PARTIAL WRITE
-            self.buffer[self.fill_count..].copy_from_slice(&buffer[..available]);
+            self.buffer.as_mut()[self.fill_count..].copy_from_slice(&buffer[..available]);
             self.fill_count += available;
             return available;
         }
 
         //FULL WRITE
-        self.buffer[self.fill_count..self.fill_count + buffer.len()].copy_from_slice(buffer);
+        self.buffer.as_mut()[self.fill_count..self.fill_count + buffer.len()]
+            .copy_from_slice(buffer);
         self.fill_count += buffer.len();
         buffer.len()
     }
@@ -201,24 +458,27 @@ impl<const S: usize> UnownedWriteBuffer<S> {
         let mut available = self.available();
         if available == 0 {
             self.push(write)?;
-            available = self.buffer.len();
+            available = self.buffer.as_ref().len();
         }
 
         if available < buffer.len() {
             //PARTIAL WRITE
-            self.buffer[self.fill_count..].copy_from_slice(&buffer[..available]);
+            self.buffer.as_mut()[self.fill_count..].copy_from_slice(&buffer[..available]);
             self.fill_count += available;
             return Ok(available);
         }
 
         //FULL WRITE
-        self.buffer[self.fill_count..self.fill_count + buffer.len()].copy_from_slice(buffer);
+        self.buffer.as_mut()[self.fill_count..self.fill_count + buffer.len()]
+            .copy_from_slice(buffer);
         self.fill_count += buffer.len();
         Ok(buffer.len())
     }
 
     /// Writes all bytes to the internal buffer if they fit,
     /// otherwise all excess bytes are flushed to the underlying Write impl.
+    /// Once the internal buffer is empty, remaining bytes that would fill the entire internal buffer
+    /// are written directly to the underlying Write impl.
     ///
     /// This fn only returns `Ok()` if all bytes are either in the internal buffer or already
     /// written to the underlying Write impl.
@@ -238,12 +498,19 @@ impl<const S: usize> UnownedWriteBuffer<S> {
 
             if available == 0 {
                 self.push(write)?;
-                available = self.buffer.len();
+                available = self.buffer.as_ref().len();
+            }
+
+            if self.fill_count == 0 && rem >= self.buffer.as_ref().len() {
+                //LARGE WRITE, going through the internal buffer would only add a copy.
+                self.write_calls += 1;
+                return write.write_all(&buffer[count..]);
             }
 
             if available < rem {
                 //PARTIAL WRITE
-                self.buffer[self.fill_count..].copy_from_slice(&buffer[count..count + available]);
+                self.buffer.as_mut()[self.fill_count..]
+                    .copy_from_slice(&buffer[count..count + available]);
                 self.fill_count += available;
                 count += available;
                 if count >= buffer.len() {
@@ -253,622 +520,4160 @@ impl<const S: usize> UnownedWriteBuffer<S> {
             }
 
             //FULL WRITE
-            self.buffer[self.fill_count..self.fill_count + rem].copy_from_slice(&buffer[count..]);
+            self.buffer.as_mut()[self.fill_count..self.fill_count + rem]
+                .copy_from_slice(&buffer[count..]);
             self.fill_count += rem;
             return Ok(());
         }
     }
 
-    /// This fn "borrows"/associates this buffer with a Write impl. The returned `BorrowedWriteBuffer`
-    /// has the same lifetime as the Write impl and &mut self combined and can be used as a dyn Write.
-    /// This might be required to call some library functions which demand a dyn Write as parameter.
-    pub fn borrow<'a, T: Write>(&'a mut self, write: &'a mut T) -> BorrowedWriteBuffer<'a, T, S> {
-        BorrowedWriteBuffer {
-            buffer: self,
-            write,
-        }
+    /// Writes all bytes of the array, see `write_all`.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    #[inline]
+    pub fn write_array<T: Write, const N: usize>(
+        &mut self,
+        write: &mut T,
+        data: [u8; N],
+    ) -> io::Result<()> {
+        self.write_all(write, &data)
     }
-}
 
-/// Borrowed dyn Write of a `UnownedWriteBuffer`.
-/// This borrowed version is directly associated with a Write impl, but is subject to lifetimes.
-pub struct BorrowedWriteBuffer<'a, T: Write, const S: usize> {
-    /// Read ref
-    buffer: &'a mut UnownedWriteBuffer<S>,
-    /// Write ref
-    write: &'a mut T,
-}
+    /// Writes all chunks in order, this is equivalent to calling `write_all` for each chunk.
+    /// Useful to write a header and a body that are stored in different slices.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl, the chunks before the failed one were written completely.
+    ///
+    pub fn push_all<T: Write>(&mut self, write: &mut T, chunks: &[&[u8]]) -> io::Result<()> {
+        for chunk in chunks {
+            self.write_all(write, chunk)?;
+        }
 
-impl<T: Write, const S: usize> Debug for BorrowedWriteBuffer<'_, T, S> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Debug::fmt(&self.buffer, f)
+        Ok(())
     }
-}
 
-impl<T: Write, const S: usize> Write for BorrowedWriteBuffer<'_, T, S> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.buffer.write(self.write, buf)
+    /// Writes the value as big endian IEEE 754 bits, NaN payloads are preserved. See `write_all`.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_f32_be<T: Write>(&mut self, write: &mut T, value: f32) -> io::Result<()> {
+        self.write_array(write, value.to_bits().to_be_bytes())
     }
 
-    fn flush(&mut self) -> io::Result<()> {
-        self.buffer.flush(self.write)
+    /// Writes the value as little endian IEEE 754 bits, NaN payloads are preserved. See `write_all`.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_f32_le<T: Write>(&mut self, write: &mut T, value: f32) -> io::Result<()> {
+        self.write_array(write, value.to_bits().to_le_bytes())
     }
 
-    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
-        self.buffer.write_all(self.write, buf)
+    /// Writes the value as big endian IEEE 754 bits, NaN payloads are preserved. See `write_all`.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_f64_be<T: Write>(&mut self, write: &mut T, value: f64) -> io::Result<()> {
+        self.write_array(write, value.to_bits().to_be_bytes())
     }
-}
-
-///
-/// Unowned Read buffer.
-///
-/// # S Generic: Size of the buffer.
-/// beware that if this size is too large, and you stack allocate this struct
-/// then you will hit the guard page and your program will crash.
-///
-///
-#[derive(Debug)]
-pub struct UnownedReadBuffer<const S: usize> {
-    /// How much have we read?
-    read_count: usize,
-    /// How much can we read?
-    fill_count: usize,
-    /// The buffer
-    buffer: [u8; S],
-}
 
-impl<const S: usize> UnownedReadBuffer<S> {
-    /// Construct a new Buffer
+    /// Writes the value as little endian IEEE 754 bits, NaN payloads are preserved. See `write_all`.
     ///
-    /// # Panics
-    /// if S is smaller than 16
-    #[must_use]
-    pub const fn new() -> Self {
-        let buf = Self {
-            read_count: 0,
-            fill_count: 0,
-            buffer: [0; S],
-        };
-
-        assert!(buf.buffer.len() >= 16, "UnownedReadBuffer is too small");
-
-        buf
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_f64_le<T: Write>(&mut self, write: &mut T, value: f64) -> io::Result<()> {
+        self.write_array(write, value.to_bits().to_le_bytes())
     }
-}
 
-impl Default for UnownedReadBuffer<0x4000> {
-    fn default() -> Self {
-        Self {
-            read_count: 0,
-            fill_count: 0,
-            buffer: [0; 0x4000],
-        }
+    /// Writes an integer in the given byte order, for example `write_int::<u32, BigEndian>(write, 1)`. See `write_all`.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_int<I: EndianInt, O: ByteOrder>(
+        &mut self,
+        write: &mut impl Write,
+        value: I,
+    ) -> io::Result<()> {
+        self.write_all(write, O::encode(value).as_ref())
     }
-}
 
-impl<const S: usize> UnownedReadBuffer<S> {
-    /// reads some bytes from the read impl.
-    fn feed<T: Read>(&mut self, read: &mut T) -> io::Result<bool> {
-        if self.read_count > 0 {
-            if self.read_count < self.fill_count {
-                self.buffer.copy_within(self.read_count..self.fill_count, 0);
+    /// Reads all bytes from src into the internal buffer and writes it to the Write impl whenever it is full.
+    /// This copies from src to the Write impl without any intermediate buffer.
+    /// Returns the amount of bytes read from src. The bytes read last remain in the internal buffer, call `flush` afterward.
+    /// `ErrorKind::Interrupted` from src is retried.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    /// Propagated from src, all bytes read until then are in the internal buffer or were written to the Write impl.
+    ///
+    pub fn pump_from<T: Write, R: Read>(&mut self, write: &mut T, src: &mut R) -> io::Result<u64> {
+        let mut count = 0u64;
+        loop {
+            if self.available() == 0 {
+                self.push(write)?;
             }
-            self.fill_count -= self.read_count;
-            self.read_count = 0;
-        }
 
-        let count = read.read(&mut self.buffer.as_mut_slice()[self.fill_count..])?;
-        if count == 0 {
-            return Ok(false);
+            match src.read(&mut self.buffer.as_mut()[self.fill_count..]) {
+                Ok(0) => return Ok(count),
+                Ok(read) => {
+                    self.fill_count += read;
+                    count += read as u64;
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
         }
-
-        self.fill_count += count;
-        Ok(true)
     }
 
-    /// returns the amount of bytes that can still be read from the internal buffer.
-    #[must_use]
-    pub const fn available(&self) -> usize {
-        self.fill_count - self.read_count
+    /// Same as `write_all` but calls progress after every call to the underlying Write impl
+    /// with the amount of bytes of buffer that were written so far.
+    /// Bytes that were already in the internal buffer before this call are not counted.
+    /// On success progress is called at least once with the length of buffer. It is never called with 0.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_all_with_progress<T: Write, F: FnMut(u64)>(
+        &mut self,
+        write: &mut T,
+        buffer: &[u8],
+        progress: F,
+    ) -> io::Result<()> {
+        let mut write = Progress::new(write, 0, self.fill_count, buffer.len(), progress);
+        self.write_all(&mut write, buffer)?;
+        write.finish(buffer.len());
+        Ok(())
     }
 
-    /// This fn will return true if at least one byte can be read.
-    /// If the internal buffer is not empty this fn immediately returns true.
-    /// If the internal buffer is empty then it will call `read()` once and return true if the read did not return Ok(0).
+    /// Same as `flush` but calls progress after every call to the underlying Write impl
+    /// with the amount of bytes of the internal buffer that were written so far.
+    /// On success progress is called at least once with the amount of bytes that were in the internal buffer.
+    /// It is never called with 0.
     ///
     /// # Errors
-    /// propagated from Read, including `TimedOut` and `WouldBlock`
-    pub fn ensure_readable<T: Read>(&mut self, read: &mut T) -> io::Result<bool> {
-        if self.available() > 0 {
-            return Ok(true);
-        }
-
-        self.feed(read)
+    /// Propagated from `Write` impl
+    ///
+    pub fn flush_with_progress<T: Write, F: FnMut(u64)>(
+        &mut self,
+        write: &mut T,
+        progress: F,
+    ) -> io::Result<()> {
+        let total = self.fill_count;
+        let mut write = Progress::new(write, 0, 0, total, progress);
+        self.flush(&mut write)?;
+        write.finish(total);
+        Ok(())
     }
 
-    /// This fn reads as many bytes as possible from the internal buffer.
-    /// it returns 0 if the internal buffer is empty.
+    /// Same as `write_all` but calls the underlying Write impl at most `max_calls` times.
+    /// All bytes go through the internal buffer, every call writes as much of the internal buffer as the Write impl accepts.
     ///
-    pub fn try_read(&mut self, buffer: &mut [u8]) -> usize {
-        if buffer.is_empty() {
-            return 0;
-        }
+    /// Returns `Budgeted::Exhausted` with the amount of bytes of buffer that were accepted
+    /// if the budget was used up or the Write impl returned `ErrorKind::WouldBlock`,
+    /// call this fn again with the remaining bytes to resume.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    /// `ErrorKind::WriteZero` if the Write impl returned Ok(0).
+    ///
+    pub fn write_all_budgeted<T: Write>(
+        &mut self,
+        write: &mut T,
+        buffer: &[u8],
+        max_calls: usize,
+    ) -> io::Result<Budgeted> {
+        let mut count = 0usize;
+        let mut calls = 0usize;
+        loop {
+            count += self.try_write::<T>(&buffer[count..]);
+            if count == buffer.len() {
+                return Ok(Budgeted::Complete);
+            }
 
-        let available = self.available();
-        if available == 0 {
-            return 0;
+            if calls == max_calls {
+                return Ok(Budgeted::Exhausted(count));
+            }
+
+            calls += 1;
+            match self.try_flush(write) {
+                //The bytes accepted so far must not be lost, resuming works the same as for an exhausted budget.
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    return Ok(Budgeted::Exhausted(count));
+                }
+                result => result?,
+            };
         }
+    }
 
-        if available >= buffer.len() {
-            //FULL READ
-            buffer.copy_from_slice(
-                &self.buffer.as_slice()[self.read_count..self.read_count + buffer.len()],
-            );
-            self.read_count += buffer.len();
-            return buffer.len();
-        }
+    /// Same as `write_all` but checks the cancel flag before every call to the underlying Write impl.
+    /// This allows aborting a long write from another thread.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    /// `ErrorKind::Interrupted` if the cancel flag was set.
+    /// In all error cases a part of the bytes may have been written to the internal buffer or the Write impl.
+    /// Bytes that are in the internal buffer remain there.
+    ///
+    pub fn write_all_cancellable<T: Write>(
+        &mut self,
+        write: &mut T,
+        buffer: &[u8],
+        cancel: &AtomicBool,
+    ) -> io::Result<()> {
+        let mut cancellable = Cancellable::new(write, cancel);
+        let result = self.write_all(&mut cancellable, buffer);
+        cancellable.finish(result)
+    }
 
-        //PARTIAL READ
-        buffer[..available]
-            .copy_from_slice(&self.buffer.as_slice()[self.read_count..self.fill_count]);
-        //The buffer is empty now.
-        self.read_count = 0;
-        self.fill_count = 0;
-        available
+    /// Same as `flush` but checks the cancel flag before every call to the underlying Write impl.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    /// `ErrorKind::Interrupted` if the cancel flag was set, the bytes that were not yet written remain in the internal buffer.
+    ///
+    pub fn flush_cancellable<T: Write>(
+        &mut self,
+        write: &mut T,
+        cancel: &AtomicBool,
+    ) -> io::Result<()> {
+        let mut cancellable = Cancellable::new(write, cancel);
+        let result = self.flush(&mut cancellable);
+        cancellable.finish(result)
     }
 
-    /// This fn will read as many bytes as possible from the internal buffer.
-    /// If the internal buffer is empty when this fn is called then 1 call to the `Read` impl is made to fill the buffer.
-    /// This fn only returns Ok(0) if the 1 call to the underlying read impl returned 0.
-    /// This fn does not call the read impl if `available()` != 0.
+    /// Writes the value as an unsigned LEB128 varint.
+    /// The encoded value is at most 10 bytes long and is written using `write_all`.
+    ///
     /// # Errors
-    /// Propagated from the `Read` impl
+    /// Propagated from `Write` impl
     ///
-    pub fn read<T: Read>(&mut self, read: &mut T, buffer: &mut [u8]) -> io::Result<usize> {
-        if buffer.is_empty() {
-            return Ok(0);
+    pub fn write_varint_u64<T: Write>(&mut self, write: &mut T, value: u64) -> io::Result<()> {
+        let mut encoded = [0u8; 10];
+        let mut len = 0usize;
+        let mut value = value;
+        loop {
+            let byte = value.to_le_bytes()[0] & 0x7F;
+            value >>= 7;
+            if value == 0 {
+                encoded[len] = byte;
+                len += 1;
+                break;
+            }
+
+            encoded[len] = byte | 0x80;
+            len += 1;
         }
 
-        let mut available = self.available();
-        if available == 0 {
-            if !self.feed(read)? {
-                return Ok(0);
-            }
+        self.write_all(write, &encoded[..len])
+    }
 
-            available = self.available();
+    /// Writes the value as an unsigned LEB128 varint.
+    /// The encoded value is at most 5 bytes long and is written using `write_all`.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_varint_u32<T: Write>(&mut self, write: &mut T, value: u32) -> io::Result<()> {
+        self.write_varint_u64(write, u64::from(value))
+    }
+
+    /// Writes the value as a zigzag encoded signed LEB128 varint, this is the encoding protobuf uses for sint64.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_varint_i64<T: Write>(&mut self, write: &mut T, value: i64) -> io::Result<()> {
+        self.write_varint_u64(write, zigzag_encode(value))
+    }
+
+    /// Writes the value as a zigzag encoded signed LEB128 varint, this is the encoding protobuf uses for sint32.
+    /// The encoding is the same as the one of `write_varint_i64` for the same value.
+    ///
+    /// # Errors
+    /// Propagated from `Write` impl
+    ///
+    pub fn write_varint_i32<T: Write>(&mut self, write: &mut T, value: i32) -> io::Result<()> {
+        self.write_varint_i64(write, i64::from(value))
+    }
+
+    /// This fn "borrows"/associates this buffer with a Write impl. The returned `BorrowedWriteBuffer`
+    /// has the same lifetime as the Write impl and &mut self combined and can be used as a dyn Write.
+    /// This might be required to call some library functions which demand a dyn Write as parameter.
+    pub const fn borrow<'a, T: Write>(
+        &'a mut self,
+        write: &'a mut T,
+    ) -> generic::BorrowedWriteBuffer<'a, T, B> {
+        generic::BorrowedWriteBuffer {
+            buffer: self,
+            write,
         }
+    }
 
-        if available >= buffer.len() {
-            //FULL READ
-            buffer.copy_from_slice(
-                &self.buffer.as_slice()[self.read_count..self.read_count + buffer.len()],
-            );
-            self.read_count += buffer.len();
-            return Ok(buffer.len());
+    /// Borrows this buffer like `borrow` and additionally passes every byte to the observer
+    /// once it was accepted by the Write impl.
+    /// See `ObservedWriteBuffer` for details.
+    pub const fn observe<'a, T: Write, F: FnMut(&[u8])>(
+        &'a mut self,
+        write: &'a mut T,
+        observer: F,
+    ) -> generic::ObservedWriteBuffer<'a, T, F, B> {
+        generic::ObservedWriteBuffer {
+            buffer: self,
+            write: ObservingWrite { write, observer },
         }
+    }
 
-        //PARTIAL READ
-        buffer[..available]
-            .copy_from_slice(&self.buffer.as_slice()[self.read_count..self.fill_count]);
-        //The buffer is empty now.
-        self.read_count = 0;
+    /// Overwrites the entire internal buffer with zeros and discards all unflushed bytes.
+    /// Use this to scrub secrets, such as passwords, that would otherwise remain in the internal buffer.
+    #[cfg(feature = "zeroize")]
+    pub fn zeroize(&mut self) {
+        zeroize::Zeroize::zeroize(self.buffer.as_mut());
         self.fill_count = 0;
-        Ok(available)
     }
+}
 
-    /// This fn will read the entire buffer from either the internal buffer or the
-    /// `Read` impl. Multiple calls to the read impl may be made if necessary to fill the buffer.
+impl Default for WriteBuffer<[u8; 0x4000]> {
+    fn default() -> Self {
+        Self {
+            fill_count: 0,
+            write_calls: 0,
+            buffer: [0; 0x4000],
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>, O: AsRef<[u8]> + AsMut<[u8]>> PartialEq<WriteBuffer<O>>
+    for WriteBuffer<B>
+{
+    /// Only the unflushed bytes are compared, the size of the buffers and stale bytes are ignored.
+    fn eq(&self, other: &WriteBuffer<O>) -> bool {
+        self.buffer.as_ref()[..self.fill_count] == other.buffer.as_ref()[..other.fill_count]
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Eq for WriteBuffer<B> {}
+
+#[cfg(feature = "zeroize")]
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Drop for WriteBuffer<B> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self.buffer.as_mut());
+    }
+}
+
+/// Borrowed dyn Write of a `UnownedWriteBuffer`.
+/// This borrowed version is directly associated with a Write impl, but is subject to lifetimes.
+pub type BorrowedWriteBuffer<'a, T, const S: usize> = generic::BorrowedWriteBuffer<'a, T, [u8; S]>;
+
+/// Same as `BorrowedWriteBuffer` but for a `DynWriteBuffer`.
+pub type BorrowedDynWriteBuffer<'a, T> = generic::BorrowedWriteBuffer<'a, T, Box<[u8]>>;
+
+impl<T: Write, B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug
+    for generic::BorrowedWriteBuffer<'_, T, B>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.buffer, f)
+    }
+}
+
+impl<T: Write, B: AsRef<[u8]> + AsMut<[u8]>> generic::BorrowedWriteBuffer<'_, T, B> {
+    /// Writes all bytes of the iterator into the internal buffer, the `Write` impl is only called when it is full.
+    /// Unlike `Extend::extend` this fn returns errors of the `Write` impl instead of panicking.
     ///
     /// # Errors
-    /// Propagated from the `Read` impl
-    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    /// Propagated from `Write` impl, all bytes taken from the iterator until then were either written or remain buffered.
     ///
-    pub fn read_exact<T: Read>(&mut self, read: &mut T, buffer: &mut [u8]) -> io::Result<()> {
-        if buffer.is_empty() {
-            return Ok(());
+    pub fn try_extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) -> io::Result<()> {
+        for byte in iter {
+            if self.buffer.is_full() {
+                self.buffer.push(self.write)?;
+            }
+
+            let idx = self.buffer.fill_count;
+            self.buffer.buffer.as_mut()[idx] = byte;
+            self.buffer.fill_count += 1;
+            if self.buffer.is_full() {
+                //Pushing right away ensures no byte taken from the iterator is lost on error.
+                self.buffer.push(self.write)?;
+            }
         }
 
-        let mut buffer = buffer;
+        Ok(())
+    }
+}
 
-        if self.available() == 0 && !self.feed(read)? {
-            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+/// Writes the bytes like `try_extend`.
+///
+/// # Panics
+/// if the `Write` impl returns an error, use `try_extend` to handle it.
+impl<T: Write, B: AsRef<[u8]> + AsMut<[u8]>> Extend<u8> for generic::BorrowedWriteBuffer<'_, T, B> {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        if let Err(err) = self.try_extend(iter) {
+            panic!("failed to extend the write buffer: {err}");
         }
+    }
+}
 
-        loop {
-            let available = self.available();
-            if available >= buffer.len() {
-                //FULL read
-                buffer.copy_from_slice(
-                    &self.buffer.as_slice()[self.read_count..self.read_count + buffer.len()],
-                );
-                self.read_count += buffer.len();
-                return Ok(());
-            }
+/// Writes the bytes like `try_extend`.
+///
+/// # Panics
+/// if the `Write` impl returns an error, use `try_extend` to handle it.
+impl<'b, T: Write, B: AsRef<[u8]> + AsMut<[u8]>> Extend<&'b u8>
+    for generic::BorrowedWriteBuffer<'_, T, B>
+{
+    fn extend<I: IntoIterator<Item = &'b u8>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
 
-            //PARTIAL READ
-            buffer[..available].copy_from_slice(
-                &self.buffer.as_slice()[self.read_count..self.read_count + available],
-            );
-            //The buffer is empty now.
-            self.read_count = 0;
-            self.fill_count = 0;
-            if !self.feed(read)? {
-                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
-            }
-            buffer = &mut buffer[available..];
+impl<T: Write, B: AsRef<[u8]> + AsMut<[u8]>> Write for generic::BorrowedWriteBuffer<'_, T, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(self.write, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.buffer.flush(self.write)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.buffer.write_all(self.write, buf)
+    }
+}
+
+/// Write of an `UnownedWriteBuffer` and a `Write` impl that passes every byte to an observer, for example to compute a checksum.
+///
+/// The observer sees the bytes exactly once and in order when the `Write` impl accepted them,
+/// regardless of whether they were flushed from the internal buffer or written directly.
+/// Bytes that are still in the internal buffer have not been observed yet.
+pub type ObservedWriteBuffer<'a, T, F, const S: usize> =
+    generic::ObservedWriteBuffer<'a, T, F, [u8; S]>;
+
+impl<T: Write, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]>>
+    generic::ObservedWriteBuffer<'_, T, F, B>
+{
+    /// Returns the observer, bytes that were not yet flushed are not observed.
+    pub fn into_observer(self) -> F {
+        self.write.observer
+    }
+}
+
+impl<T: Write, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug
+    for generic::ObservedWriteBuffer<'_, T, F, B>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.buffer, f)
+    }
+}
+
+impl<T: Write, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]>> Write
+    for generic::ObservedWriteBuffer<'_, T, F, B>
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(&mut self.write, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.buffer.flush(&mut self.write)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.buffer.write_all(&mut self.write, buf)
+    }
+}
+
+/// Read/Write impl that reports the progress of an operation after every call to the inner impl.
+struct Progress<'a, T, F: FnMut(u64)> {
+    /// read or write ref
+    inner: &'a mut T,
+    /// bytes of the operation that were done before the first call
+    done: usize,
+    /// bytes transferred by the first calls that do not belong to the operation
+    skip: usize,
+    /// total bytes of the operation
+    total: usize,
+    /// the last reported value
+    reported: u64,
+    /// the callback
+    callback: F,
+}
+
+impl<'a, T, F: FnMut(u64)> Progress<'a, T, F> {
+    /// Constructor
+    const fn new(inner: &'a mut T, done: usize, skip: usize, total: usize, progress: F) -> Self {
+        Self {
+            inner,
+            done,
+            skip,
+            total,
+            reported: 0,
+            callback: progress,
         }
     }
 
-    /// Reads until either EOF happens or the desired byte is found.
-    /// This fn may call the underlying `Read` impl multiple times until the buffer is filled.
-    ///
-    /// # Errors
-    /// Propagated from the `Read` impl
-    ///
-    pub fn read_until<T: Read>(
-        &mut self,
-        read: &mut T,
-        byte: u8,
-        buf: &mut Vec<u8>,
-    ) -> io::Result<usize> {
-        let mut count: usize = 0;
+    /// Records count transferred bytes and reports the new progress.
+    fn advance(&mut self, count: usize) {
+        let skipped = count.min(self.skip);
+        self.skip -= skipped;
+        self.done = self.done.saturating_add(count - skipped).min(self.total);
+        self.report(self.done);
+    }
 
-        if self.available() == 0 && !self.feed(read)? {
-            return Ok(0);
+    /// Reports the final amount unless it was already reported.
+    fn finish(mut self, done: usize) {
+        self.report(done);
+    }
+
+    /// Calls the callback if value is not 0 and differs from the last reported value.
+    fn report(&mut self, value: usize) {
+        let value = value as u64;
+        if value != 0 && value != self.reported {
+            self.reported = value;
+            (self.callback)(value);
         }
+    }
+}
 
-        loop {
-            for idx in self.read_count..self.fill_count {
-                if self.buffer[idx] == byte {
-                    let to_push = &self.buffer[self.read_count..=idx];
-                    buf.extend_from_slice(to_push);
-                    self.read_count += to_push.len();
-                    return Ok(count + to_push.len());
-                }
-            }
+impl<T: Read, F: FnMut(u64)> Read for Progress<'_, T, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.advance(count);
+        Ok(count)
+    }
+}
 
-            let to_push = &self.buffer[self.read_count..self.fill_count];
-            buf.extend_from_slice(to_push);
-            count += to_push.len();
-            self.read_count = 0;
-            self.fill_count = 0;
-            if !self.feed(read)? {
-                return Ok(count);
-            }
+impl<T: Write, F: FnMut(u64)> Write for Progress<'_, T, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.inner.write(buf)?;
+        self.advance(count);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Read/Write impl that fails once the cancel flag is set.
+/// The error is only turned into `ErrorKind::Interrupted` by `finish`,
+/// the buffers and the default impls of std retry Interrupted, which would never end once the flag is set.
+struct Cancellable<'a, T> {
+    /// read or write ref
+    inner: &'a mut T,
+    /// the cancel flag
+    cancel: &'a AtomicBool,
+    /// did a call fail because of the cancel flag?
+    cancelled: bool,
+}
+
+impl<'a, T> Cancellable<'a, T> {
+    /// Wraps inner.
+    const fn new(inner: &'a mut T, cancel: &'a AtomicBool) -> Self {
+        Self {
+            inner,
+            cancel,
+            cancelled: false,
         }
     }
 
-    /// Reads until either EOF happens or the desired byte is found or limit bytes have been appended to buf.
-    /// The actual read impl may supply more bytes than limit, the excess is stored in the internal buffer in this case.
-    /// Returns the amount of bytes appended to the buf vec.
-    ///
-    /// # Errors
-    /// Propagated from the `Read` impl
+    /// Returns an error if the cancel flag is set.
+    fn check(&mut self) -> io::Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            self.cancelled = true;
+            return Err(io::Error::from(ErrorKind::Other));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the result of the operation with `ErrorKind::Interrupted` if it was cancelled.
+    fn finish<R>(&self, result: io::Result<R>) -> io::Result<R> {
+        if self.cancelled {
+            return Err(io::Error::new(
+                ErrorKind::Interrupted,
+                "operation cancelled",
+            ));
+        }
+
+        result
+    }
+}
+
+impl<T: Read> Read for Cancellable<'_, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check()?;
+        self.inner.read(buf)
+    }
+}
+
+impl<T: Write> Write for Cancellable<'_, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check()?;
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.check()?;
+        self.inner.flush()
+    }
+}
+
+/// Write impl that passes all bytes accepted by the inner Write impl to the observer.
+struct ObservingWrite<'a, T: Write, F: FnMut(&[u8])> {
+    /// write ref
+    write: &'a mut T,
+    /// the observer
+    observer: F,
+}
+
+impl<T: Write, F: FnMut(&[u8])> Write for ObservingWrite<'_, T, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.write.write(buf)?;
+        (self.observer)(&buf[..count.min(buf.len())]);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+}
+
+/// Write buffer that owns its `Write` impl, similar to `std::io::BufWriter`.
+///
+/// The buffered bytes are flushed when this struct is dropped.
+/// Errors that occur during this flush are passed to the handler set with `set_drop_error_handler`,
+/// without a handler they are ignored. Call `flush` or `into_inner` before dropping to observe them directly.
+pub struct OwnedWriteBuffer<W: Write, const S: usize> {
+    /// the buffer
+    buffer: UnownedWriteBuffer<S>,
+    /// the write impl, only None after `into_inner` took it
+    write: Option<W>,
+    /// receives the error of the flush on drop
+    drop_error_handler: Option<DropErrorHandler>,
+}
+
+/// Handler that receives the error of the flush an `OwnedWriteBuffer` does when it is dropped.
+pub type DropErrorHandler = Box<dyn FnMut(io::Error) + Send + Sync>;
+
+impl<W: Write, const S: usize> OwnedWriteBuffer<W, S> {
+    /// Construct a new Buffer that owns the given `Write` impl.
     ///
-    pub fn read_until_limit<T: Read>(
+    /// # Compile errors
+    /// if S is smaller than 16.
+    #[must_use]
+    pub const fn new(write: W) -> Self {
+        Self {
+            buffer: UnownedWriteBuffer::new(),
+            write: Some(write),
+            drop_error_handler: None,
+        }
+    }
+
+    /// Sets the handler that receives the error if flushing the internal buffer fails when this buffer is dropped.
+    /// The handler is not called if the flush succeeds or if `into_inner` already took the `Write` impl.
+    /// Returns the previous handler.
+    pub fn set_drop_error_handler(
         &mut self,
-        read: &mut T,
+        handler: impl FnMut(io::Error) + Send + Sync + 'static,
+    ) -> Option<DropErrorHandler> {
+        self.drop_error_handler.replace(Box::new(handler))
+    }
+
+    /// Removes and returns the handler set with `set_drop_error_handler`.
+    /// Errors of the flush on drop are ignored again afterwards.
+    pub fn take_drop_error_handler(&mut self) -> Option<DropErrorHandler> {
+        self.drop_error_handler.take()
+    }
+
+    /// Returns a reference to the `Write` impl.
+    pub fn get_ref(&self) -> &W {
+        self.write
+            .as_ref()
+            .unwrap_or_else(|| unreachable!("write is only taken by into_inner"))
+    }
+
+    /// Returns a mutable reference to the `Write` impl.
+    /// Writing to it directly bypasses the bytes that are still in the internal buffer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.write
+            .as_mut()
+            .unwrap_or_else(|| unreachable!("write is only taken by into_inner"))
+    }
+
+    /// Flushes the internal buffer and returns the `Write` impl.
+    ///
+    /// # Errors
+    /// If the flush fails then the error and this buffer, including the bytes that were not flushed, are returned.
+    ///
+    pub fn into_inner(mut self) -> Result<W, IntoInnerError<Self>> {
+        if let Err(error) = self.flush() {
+            return Err(IntoInnerError { inner: self, error });
+        }
+
+        Ok(self
+            .write
+            .take()
+            .unwrap_or_else(|| unreachable!("write is only taken by into_inner")))
+    }
+}
+
+impl<W: Write + Debug, const S: usize> Debug for OwnedWriteBuffer<W, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OwnedWriteBuffer")
+            .field("buffer", &self.buffer)
+            .field("write", &self.write)
+            .field("drop_error_handler", &self.drop_error_handler.is_some())
+            .finish()
+    }
+}
+
+impl<W: Write, const S: usize> Write for OwnedWriteBuffer<W, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let write = self.write.as_mut().ok_or(ErrorKind::Other)?;
+        self.buffer.write(write, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let write = self.write.as_mut().ok_or(ErrorKind::Other)?;
+        self.buffer.flush(write)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let write = self.write.as_mut().ok_or(ErrorKind::Other)?;
+        self.buffer.write_all(write, buf)
+    }
+}
+
+impl<W: Write, const S: usize> Drop for OwnedWriteBuffer<W, S> {
+    fn drop(&mut self) {
+        if let Some(write) = self.write.as_mut() {
+            if let Err(error) = self.buffer.flush(write) {
+                if let Some(handler) = self.drop_error_handler.as_mut() {
+                    handler(error);
+                }
+            }
+        }
+    }
+}
+
+/// Error returned by `OwnedWriteBuffer::into_inner` if flushing the internal buffer failed.
+/// Contains the error and the buffer so no data is lost.
+#[derive(Debug)]
+pub struct IntoInnerError<W> {
+    /// the buffer
+    inner: W,
+    /// the error of the flush
+    error: io::Error,
+}
+
+impl<W> IntoInnerError<W> {
+    /// Returns the error that occurred while flushing.
+    pub const fn error(&self) -> &io::Error {
+        &self.error
+    }
+
+    /// Returns the error that occurred while flushing.
+    pub fn into_error(self) -> io::Error {
+        self.error
+    }
+
+    /// Returns the buffer that could not be flushed.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns the error and the buffer that could not be flushed.
+    pub fn into_parts(self) -> (io::Error, W) {
+        (self.error, self.inner)
+    }
+}
+
+impl<W> Display for IntoInnerError<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.error, f)
+    }
+}
+
+impl<W: Debug> core::error::Error for IntoInnerError<W> {}
+
+///
+/// Unowned Read buffer backed by an array.
+///
+/// # S Generic: Size of the buffer.
+/// beware that if this size is too large, and you stack allocate this struct
+/// then you will hit the guard page and your program will crash.
+/// If you must use very large buffers then use a `DynReadBuffer` or `UnownedReadBuffer::new_boxed`.
+///
+///
+pub type UnownedReadBuffer<const S: usize> = ReadBuffer<[u8; S]>;
+
+///
+/// Unowned Read buffer backed by a heap allocated slice.
+/// The size of the buffer is chosen at runtime by calling `DynReadBuffer::with_capacity`.
+///
+pub type DynReadBuffer = ReadBuffer<Box<[u8]>>;
+
+impl<const S: usize> ReadBuffer<[u8; S]> {
+    /// Construct a new Buffer
+    ///
+    /// # Compile errors
+    /// if S is smaller than 16
+    /// ```compile_fail
+    /// let _ = unowned_buf::UnownedReadBuffer::<8>::new();
+    /// ```
+    #[must_use]
+    pub const fn new() -> Self {
+        const { assert!(S >= 16, "UnownedReadBuffer is too small") };
+
+        Self {
+            read_count: 0,
+            fill_count: 0,
+            generation: 0,
+            compaction_threshold: 0,
+            read_calls: 0,
+            buffer: [0; S],
+        }
+    }
+
+    /// Construct a new `DynReadBuffer` of size S, the buffer is allocated directly on the heap.
+    /// Unlike `Box::new(UnownedReadBuffer::new())` the buffer is never placed on the stack,
+    /// this is the recommended way to construct buffers larger than a few KiB.
+    ///
+    /// # Compile errors
+    /// if S is smaller than 16.
+    #[must_use]
+    pub fn new_boxed() -> DynReadBuffer {
+        const { assert!(S >= 16, "UnownedReadBuffer is too small") };
+
+        DynReadBuffer::with_capacity(S)
+    }
+
+    /// Returns the size of the buffer.
+    #[must_use]
+    #[allow(clippy::unused_self)] //Part of the public API through the type alias.
+    pub const fn size(&self) -> usize {
+        S
+    }
+
+    /// Decomposes this buffer into the backing array, the read count and the fill count.
+    /// The unread bytes are `array[read_count..fill_count]`.
+    /// With the `zeroize` feature the internal buffer is still zeroized, the returned copy is not.
+    #[must_use]
+    #[cfg_attr(not(feature = "zeroize"), allow(clippy::missing_const_for_fn))]
+    pub fn into_parts(self) -> ([u8; S], usize, usize) {
+        (self.buffer, self.read_count, self.fill_count)
+    }
+
+    /// Constructs a buffer from parts previously returned by `into_parts`.
+    ///
+    /// # Panics
+    /// if `read_count <= fill_count <= S` does not hold.
+    ///
+    /// # Compile errors
+    /// if S is smaller than 16.
+    #[must_use]
+    pub const fn from_parts(buffer: [u8; S], read_count: usize, fill_count: usize) -> Self {
+        const { assert!(S >= 16, "UnownedReadBuffer is too small") };
+        assert!(
+            read_count <= fill_count,
+            "read_count is larger than fill_count"
+        );
+        assert!(fill_count <= S, "fill_count is larger than the buffer");
+
+        Self {
+            read_count,
+            fill_count,
+            generation: 0,
+            compaction_threshold: 0,
+            read_calls: 0,
+            buffer,
+        }
+    }
+}
+
+impl ReadBuffer<Box<[u8]>> {
+    /// Construct a new heap allocated Buffer of the given size.
+    ///
+    /// # Panics
+    /// if size is smaller than 16
+    #[must_use]
+    pub fn with_capacity(size: usize) -> Self {
+        assert!(size >= 16, "DynReadBuffer is too small");
+        Self {
+            read_count: 0,
+            fill_count: 0,
+            generation: 0,
+            compaction_threshold: 0,
+            read_calls: 0,
+            buffer: vec![0; size].into_boxed_slice(),
+        }
+    }
+
+    /// Returns the size of the buffer.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Decomposes this buffer into the backing slice, the read count and the fill count.
+    /// The unread bytes are `slice[read_count..fill_count]`.
+    #[must_use]
+    pub fn into_parts(mut self) -> (Box<[u8]>, usize, usize) {
+        (
+            core::mem::take(&mut self.buffer),
+            self.read_count,
+            self.fill_count,
+        )
+    }
+
+    /// Constructs a buffer from parts previously returned by `into_parts`.
+    ///
+    /// # Panics
+    /// if the buffer is smaller than 16 or `read_count <= fill_count <= buffer.len()` does not hold.
+    #[must_use]
+    pub fn from_parts(buffer: Box<[u8]>, read_count: usize, fill_count: usize) -> Self {
+        assert!(buffer.len() >= 16, "DynReadBuffer is too small");
+        assert!(
+            read_count <= fill_count,
+            "read_count is larger than fill_count"
+        );
+        assert!(
+            fill_count <= buffer.len(),
+            "fill_count is larger than the buffer"
+        );
+
+        Self {
+            read_count,
+            fill_count,
+            generation: 0,
+            compaction_threshold: 0,
+            read_calls: 0,
+            buffer,
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> ReadBuffer<B> {
+    /// Discards all bytes in the internal buffer and returns how many bytes were discarded.
+    /// Use this to resynchronize after a protocol error, subsequent reads come straight from the `Read` impl.
+    /// This invalidates all marks.
+    pub const fn clear(&mut self) -> usize {
+        let discarded = self.available();
+        self.read_count = 0;
+        self.fill_count = 0;
+        self.generation = self.generation.wrapping_add(1);
+        discarded
+    }
+
+    /// Appends all unread bytes of the internal buffer to out and empties it, returns how many bytes were appended.
+    /// This fn never calls the underlying `Read` impl. Use this to hand the already received bytes
+    /// to another layer before reading from the `Read` impl without this buffer.
+    /// This invalidates all marks.
+    pub fn drain_buffered(&mut self, out: &mut Vec<u8>) -> usize {
+        out.extend_from_slice(&self.buffer.as_ref()[self.read_count..self.fill_count]);
+        self.clear()
+    }
+
+    /// Discards up to n bytes from the front of the internal buffer, these are the bytes that would be read next.
+    /// Unlike `consume` this fn never panics. Returns how many bytes were discarded.
+    pub fn truncate_front(&mut self, n: usize) -> usize {
+        let discarded = n.min(self.available());
+        self.read_count += discarded;
+        discarded
+    }
+
+    /// Discards up to n bytes from the back of the internal buffer, these are the bytes that were received last.
+    /// Returns how many bytes were discarded.
+    pub fn truncate_back(&mut self, n: usize) -> usize {
+        let discarded = n.min(self.available());
+        self.fill_count -= discarded;
+        discarded
+    }
+
+    /// Moves the unread bytes to the start of the internal buffer.
+    /// This invalidates all marks if any bytes were already read.
+    fn compact(&mut self) {
+        if self.read_count > 0 {
+            if self.read_count < self.fill_count {
+                self.buffer
+                    .as_mut()
+                    .copy_within(self.read_count..self.fill_count, 0);
+            }
+            self.fill_count -= self.read_count;
+            self.read_count = 0;
+            self.generation = self.generation.wrapping_add(1);
+        }
+    }
+
+    /// reads some bytes from the read impl.
+    fn feed<T: Read>(&mut self, read: &mut T) -> io::Result<bool> {
+        self.feed_limited(read, usize::MAX)
+    }
+
+    /// reads at most max bytes from the read impl.
+    fn feed_limited<T: Read>(&mut self, read: &mut T, max: usize) -> io::Result<bool> {
+        if self.available() == 0 || self.available_space() < self.compaction_threshold() {
+            self.compact();
+        }
+
+        let end = self.fill_count + max.min(self.available_space());
+        let count = loop {
+            self.read_calls += 1;
+            match read.read(&mut self.buffer.as_mut()[self.fill_count..end]) {
+                //Like std, Interrupted is retried.
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                result => break result?,
+            }
+        };
+        if count == 0 {
+            return Ok(false);
+        }
+
+        self.fill_count += count;
+        Ok(true)
+    }
+
+    /// returns the amount of bytes that can still be read from the internal buffer.
+    #[must_use]
+    pub const fn available(&self) -> usize {
+        self.fill_count - self.read_count
+    }
+
+    /// Returns how often this buffer called `Read::read` of a `Read` impl.
+    /// Handing the remainder of a stream to `Read::read_to_end` of the `Read` impl counts as a single call.
+    /// Useful to verify that the size of the internal buffer is large enough to save calls.
+    #[must_use]
+    pub const fn underlying_read_calls(&self) -> u64 {
+        self.read_calls
+    }
+
+    /// returns the amount of bytes that can still be read from the internal buffer, same as `available()`.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.available()
+    }
+
+    /// returns true if there are no bytes left to read in the internal buffer.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.available() == 0
+    }
+
+    /// returns the amount of bytes the next read from the `Read` impl can append without compacting the internal buffer.
+    #[must_use]
+    pub fn available_space(&self) -> usize {
+        self.buffer.as_ref().len() - self.fill_count
+    }
+
+    /// Sets how many bytes must at least be free at the end of the internal buffer
+    /// before bytes are read from the `Read` impl without compacting the internal buffer first.
+    /// Compacting moves the unread bytes to the start of the internal buffer.
+    /// A higher threshold compacts more often, a threshold of 1 or 0 only compacts when the end of the internal buffer is reached.
+    /// A threshold of the buffer size compacts before every read.
+    /// An internal buffer without unread bytes is always compacted, because no bytes have to be moved in that case.
+    /// By default the threshold is a quarter of the buffer size.
+    /// This only affects how often bytes are moved and how long marks stay valid, never which bytes are read.
+    pub fn set_compaction_threshold(&mut self, threshold: usize) {
+        self.compaction_threshold = threshold.max(1);
+    }
+
+    /// Returns the threshold set with `set_compaction_threshold`, a quarter of the size of the internal buffer by default.
+    #[must_use]
+    pub fn compaction_threshold(&self) -> usize {
+        if self.compaction_threshold == 0 {
+            return self.buffer.as_ref().len() / 4;
+        }
+
+        self.compaction_threshold
+    }
+
+    /// returns the amount of bytes that could be added to the internal buffer after it was compacted.
+    /// Compacting reclaims the space of all bytes that were already read.
+    #[must_use]
+    pub fn total_free(&self) -> usize {
+        self.buffer.as_ref().len() - self.available()
+    }
+
+    /// returns true if no bytes can be added to the internal buffer, even after compacting it.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.total_free() == 0
+    }
+
+    /// returns true if n more bytes can be added to the internal buffer after it was compacted.
+    #[must_use]
+    pub fn would_fit(&self, n: usize) -> bool {
+        n <= self.total_free()
+    }
+
+    /// Appends data to the unread bytes of the internal buffer, as if it had been read from the `Read` impl.
+    /// This can be used to re-inject bytes that were already consumed.
+    /// The internal buffer is compacted if data does not fit into `available_space()`,
+    /// so all bytes that were already read are reclaimed before giving up.
+    ///
+    /// # Panics
+    /// if data does not fit into the internal buffer even after compacting it, see `would_fit` and `try_copy_into_internal_buffer`.
+    ///
+    pub fn copy_into_internal_buffer(&mut self, data: &[u8]) {
+        if let Err(free) = self.try_copy_into_internal_buffer(data) {
+            panic!(
+                "{} bytes do not fit into the internal buffer, {} bytes are free at its end and {free} bytes after compacting it",
+                data.len(),
+                self.available_space()
+            );
+        }
+    }
+
+    /// Same as `copy_into_internal_buffer` but returns the amount of free bytes
+    /// instead of panicking if data does not fit. Nothing is copied in this case.
+    ///
+    /// # Errors
+    /// the amount of bytes that would fit if data is larger than that.
+    ///
+    pub fn try_copy_into_internal_buffer(&mut self, data: &[u8]) -> Result<(), usize> {
+        let free = self.total_free();
+        if data.len() > free {
+            return Err(free);
+        }
+
+        if data.len() > self.available_space() {
+            self.compact();
+        }
+
+        self.buffer.as_mut()[self.fill_count..self.fill_count + data.len()].copy_from_slice(data);
+        self.fill_count += data.len();
+        Ok(())
+    }
+
+    /// Moves as many unread bytes of other into the internal buffer of self as fit and consumes them from other.
+    /// Returns the amount of moved bytes. The bytes are appended after the unread bytes of self.
+    /// This is useful when switching to a buffer of a different size without losing already buffered bytes.
+    pub fn transfer_from<O: AsRef<[u8]> + AsMut<[u8]>>(
+        &mut self,
+        other: &mut ReadBuffer<O>,
+    ) -> usize {
+        let count = other.available().min(self.total_free());
+        let result = self.try_copy_into_internal_buffer(&other.internal_buffer()[..count]);
+        debug_assert!(result.is_ok());
+        other.consume(count);
+        count
+    }
+
+    /// This fn will return true if at least one byte can be read.
+    /// If the internal buffer is not empty this fn immediately returns true.
+    /// If the internal buffer is empty then it will call `read()` once and return true if the read did not return Ok(0).
+    ///
+    /// # Errors
+    /// propagated from Read, including `TimedOut` and `WouldBlock`
+    pub fn ensure_readable<T: Read>(&mut self, read: &mut T) -> io::Result<bool> {
+        if self.available() > 0 {
+            return Ok(true);
+        }
+
+        self.feed(read)
+    }
+
+    /// Calls the `Read` impl until at least n bytes are in the internal buffer.
+    /// Returns false if EOF occurred before that, the bytes read until then remain in the internal buffer.
+    /// If the internal buffer already contains n bytes then this fn immediately returns true.
+    ///
+    /// # Errors
+    /// propagated from Read, including `TimedOut` and `WouldBlock`
+    /// `ErrorKind::InvalidInput` if n is larger than the internal buffer.
+    pub fn ensure_readable_at_least<T: Read>(
+        &mut self,
+        read: &mut T,
+        n: usize,
+    ) -> io::Result<bool> {
+        Ok(self.fill_buf_at_least(read, n)?.len() >= n)
+    }
+
+    /// Same as `ensure_readable` but distinguishes between buffered bytes, EOF and a `Read` impl that is not ready.
+    /// `ErrorKind::WouldBlock` from the `Read` impl is returned as `ReadState::NotReady`.
+    /// This is intended for non-blocking readers driven by a poll loop.
+    ///
+    /// # Errors
+    /// propagated from Read, except for `WouldBlock`
+    pub fn poll_readable<T: Read>(&mut self, read: &mut T) -> io::Result<ReadState> {
+        match self.ensure_readable(read) {
+            Ok(true) => Ok(ReadState::Ready(self.available())),
+            Ok(false) => Ok(ReadState::Eof),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(ReadState::NotReady),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns true if at least one byte is in the internal buffer or can be read from the `Read` impl.
+    /// Returns false only if the internal buffer is empty and the `Read` impl returned Ok(0).
+    /// EOF is not sticky, a later call will call the `Read` impl again.
+    /// Equivalent to `BufRead::has_data_left`.
+    ///
+    /// # Errors
+    /// propagated from Read, including `TimedOut` and `WouldBlock`
+    pub fn has_data_left<T: Read>(&mut self, read: &mut T) -> io::Result<bool> {
+        self.ensure_readable(read)
+    }
+
+    /// This fn reads as many bytes as possible from the internal buffer.
+    /// it returns 0 if the internal buffer is empty.
+    ///
+    pub fn try_read(&mut self, buffer: &mut [u8]) -> usize {
+        if buffer.is_empty() {
+            return 0;
+        }
+
+        let available = self.available();
+        if available == 0 {
+            return 0;
+        }
+
+        if available >= buffer.len() {
+            //FULL READ
+            buffer.copy_from_slice(
+                &self.buffer.as_ref()[self.read_count..self.read_count + buffer.len()],
+            );
+            self.read_count += buffer.len();
+            return buffer.len();
+        }
+
+        //PARTIAL READ
+        buffer[..available]
+            .copy_from_slice(&self.buffer.as_ref()[self.read_count..self.fill_count]);
+        //The buffer is empty now, the bytes stay where they are so marks remain valid.
+        self.read_count = self.fill_count;
+        available
+    }
+
+    /// This fn will read as many bytes as possible from the internal buffer.
+    /// If the internal buffer is empty when this fn is called then 1 call to the `Read` impl is made to fill the buffer.
+    /// This fn only returns Ok(0) if the 1 call to the underlying read impl returned 0.
+    /// This fn does not call the read impl if `available()` != 0.
+    /// If the internal buffer is empty and the buffer is at least as large as the internal buffer
+    /// then the `Read` impl reads directly into the buffer.
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read<T: Read>(&mut self, read: &mut T, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let mut available = self.available();
+        if available == 0 {
+            if buffer.len() >= self.buffer.as_ref().len() {
+                //LARGE READ, going through the internal buffer would only add a copy.
+                self.read_calls += 1;
+                return read.read(buffer);
+            }
+
+            if !self.feed(read)? {
+                return Ok(0);
+            }
+
+            available = self.available();
+        }
+
+        if available >= buffer.len() {
+            //FULL READ
+            buffer.copy_from_slice(
+                &self.buffer.as_ref()[self.read_count..self.read_count + buffer.len()],
+            );
+            self.read_count += buffer.len();
+            return Ok(buffer.len());
+        }
+
+        //PARTIAL READ
+        buffer[..available]
+            .copy_from_slice(&self.buffer.as_ref()[self.read_count..self.fill_count]);
+        //The buffer is empty now, the bytes stay where they are so marks remain valid.
+        self.read_count = self.fill_count;
+        Ok(available)
+    }
+
+    /// Same as `read` but scatters the bytes across multiple buffers, they are filled in order.
+    /// The `Read` impl is called at most once and only if the internal buffer is empty.
+    /// If the internal buffer is empty and the buffers are together at least as large as the internal buffer
+    /// then the `Read` impl reads directly into the buffers using `Read::read_vectored`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    #[cfg(feature = "std")]
+    pub fn read_vectored<T: Read>(
+        &mut self,
+        read: &mut T,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> io::Result<usize> {
+        let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+        if total == 0 {
+            return Ok(0);
+        }
+
+        if self.available() == 0 {
+            if total >= self.buffer.as_ref().len() {
+                //LARGE READ, going through the internal buffer would only add a copy.
+                self.read_calls += 1;
+                return read.read_vectored(bufs);
+            }
+
+            if !self.feed(read)? {
+                return Ok(0);
+            }
+        }
+
+        let mut count = 0usize;
+        for buf in bufs {
+            count += self.try_read(buf);
+            if self.available() == 0 {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// This fn will read the entire buffer from either the internal buffer or the
+    /// `Read` impl. Multiple calls to the read impl may be made if necessary to fill the buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    ///
+    pub fn read_exact<T: Read>(&mut self, read: &mut T, buffer: &mut [u8]) -> io::Result<()> {
+        self.read_exact_counted(read, buffer)
+            .map_err(|(_, err)| err)
+    }
+
+    /// Same as `read_exact` but on error also returns how many bytes were already copied into the buffer.
+    /// Those bytes are consumed and can be found at the start of the buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    ///
+    pub fn read_exact_counted<T: Read>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+    ) -> Result<(), (usize, io::Error)> {
+        let mut count = 0usize;
+        while count < buffer.len() {
+            if self.available() == 0 && buffer.len() - count >= self.buffer.as_ref().len() {
+                //LARGE READ, the remainder is read directly into the buffer.
+                self.read_calls += 1;
+                match read.read(&mut buffer[count..]) {
+                    Ok(0) => return Err((count, io::Error::from(ErrorKind::UnexpectedEof))),
+                    Ok(copied) => count += copied,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err((count, err)),
+                }
+                continue;
+            }
+
+            if self.available() == 0 {
+                match self.feed(read) {
+                    Ok(true) => {}
+                    Ok(false) => return Err((count, io::Error::from(ErrorKind::UnexpectedEof))),
+                    Err(err) => return Err((count, err)),
+                }
+            }
+
+            count += self.try_read(&mut buffer[count..]);
+        }
+
+        Ok(())
+    }
+
+    /// Same as `read_exact_counted` but nothing is consumed on error if buffer is not larger than the internal buffer.
+    /// All bytes are buffered in the internal buffer before any of them are copied into buffer,
+    /// so a failed call can simply be retried, for example after `ErrorKind::WouldBlock`.
+    /// Larger buffers are read like `read_exact_counted`, the error then contains how many bytes were already
+    /// copied into the buffer and consumed.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    ///
+    pub fn read_exact_or_restore<T: Read>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+    ) -> Result<(), (usize, io::Error)> {
+        if buffer.len() > self.buffer.as_ref().len() {
+            return self.read_exact_counted(read, buffer);
+        }
+
+        while self.available() < buffer.len() {
+            match self.feed(read) {
+                Ok(true) => {}
+                Ok(false) => return Err((0, io::Error::from(ErrorKind::UnexpectedEof))),
+                Err(err) => return Err((0, err)),
+            }
+        }
+
+        let count = self.try_read(buffer);
+        debug_assert_eq!(count, buffer.len());
+        Ok(())
+    }
+
+    /// Same as `read_exact` but calls the `Read` impl at most `max_calls` times.
+    ///
+    /// Returns `Budgeted::Exhausted` with the amount of bytes that were copied into buffer
+    /// if the budget was used up or the `Read` impl returned `ErrorKind::WouldBlock`.
+    /// Those bytes are consumed, call this fn again with the rest of the buffer to resume.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    ///
+    pub fn read_exact_budgeted<T: Read>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+        max_calls: usize,
+    ) -> io::Result<Budgeted> {
+        let mut count = 0usize;
+        let mut calls = 0usize;
+        while count < buffer.len() {
+            if self.available() == 0 {
+                if calls == max_calls {
+                    return Ok(Budgeted::Exhausted(count));
+                }
+
+                calls += 1;
+                match self.feed(read) {
+                    Ok(true) => {}
+                    Ok(false) => return Err(io::Error::from(ErrorKind::UnexpectedEof)),
+                    //The bytes copied so far must not be lost, resuming works the same as for an exhausted budget.
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                        return Ok(Budgeted::Exhausted(count));
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+
+            count += self.try_read(&mut buffer[count..]);
+        }
+
+        Ok(Budgeted::Complete)
+    }
+
+    /// Same as `read_exact` but calls progress after every call to the `Read` impl
+    /// with the amount of bytes of buffer that are filled so far.
+    /// On success progress is called at least once with the length of buffer. It is never called with 0.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    ///
+    pub fn read_exact_with_progress<T: Read, F: FnMut(u64)>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+        progress: F,
+    ) -> io::Result<()> {
+        let mut read = Progress::new(read, self.available(), 0, buffer.len(), progress);
+        self.read_exact(&mut read, buffer)?;
+        read.finish(buffer.len());
+        Ok(())
+    }
+
+    /// Same as `read_to_end` but calls progress after every call to the `Read` impl
+    /// with the amount of bytes that were read so far, including the bytes that were already in the internal buffer.
+    /// On success progress is called at least once with the returned amount. It is never called with 0.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_to_end_with_progress<T: Read, F: FnMut(u64)>(
+        &mut self,
+        read: &mut T,
+        buf: &mut Vec<u8>,
+        progress: F,
+    ) -> io::Result<usize> {
+        let mut read = Progress::new(read, self.available(), 0, usize::MAX, progress);
+        let count = self.read_to_end(&mut read, buf)?;
+        read.finish(count);
+        Ok(count)
+    }
+
+    /// Same as `read_to_string` but calls progress after every call to the `Read` impl
+    /// with the amount of bytes that were read so far, including the bytes that were already in the internal buffer.
+    /// On success progress is called at least once with the returned amount. It is never called with 0.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. See `read_to_string`.
+    ///
+    pub fn read_to_string_with_progress<T: Read, F: FnMut(u64)>(
+        &mut self,
+        read: &mut T,
+        buf: &mut String,
+        progress: F,
+    ) -> io::Result<usize> {
+        let mut read = Progress::new(read, self.available(), 0, usize::MAX, progress);
+        let count = self.read_to_string(&mut read, buf)?;
+        read.finish(count);
+        Ok(count)
+    }
+
+    /// Same as `read_exact` but checks the cancel flag before every call to the `Read` impl.
+    /// This allows aborting a long read from another thread.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    /// `ErrorKind::Interrupted` if the cancel flag was set.
+    /// In all error cases the bytes that were read until then are consumed and can be found at the start of the buffer,
+    /// the rest of the buffer is untouched.
+    ///
+    pub fn read_exact_cancellable<T: Read>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+        cancel: &AtomicBool,
+    ) -> io::Result<()> {
+        let mut cancellable = Cancellable::new(read, cancel);
+        let result = self.read_exact(&mut cancellable, buffer);
+        cancellable.finish(result)
+    }
+
+    /// Same as `read_to_end` but checks the cancel flag before every call to the `Read` impl.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::Interrupted` if the cancel flag was set.
+    /// In all error cases the bytes that were read until then are consumed and appended to buf.
+    ///
+    pub fn read_to_end_cancellable<T: Read>(
+        &mut self,
+        read: &mut T,
+        buf: &mut Vec<u8>,
+        cancel: &AtomicBool,
+    ) -> io::Result<usize> {
+        let mut cancellable = Cancellable::new(read, cancel);
+        let result = self.read_to_end(&mut cancellable, buf);
+        cancellable.finish(result)
+    }
+
+    /// Continues the exact read described by state and copies bytes into the not yet filled part of buffer.
+    /// Returns Ok(true) once all `state.len()` bytes of buffer are filled,
+    /// returns Ok(false) if the `Read` impl returned `ErrorKind::WouldBlock`, call this fn again later to resume.
+    /// The state always reflects the bytes that were copied into buffer, those bytes are consumed.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl, except for `WouldBlock`
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    ///
+    /// # Panics
+    /// if buffer is shorter than `state.len()`.
+    ///
+    pub fn resume_read_exact<T: Read>(
+        &mut self,
+        read: &mut T,
+        state: &mut ReadExactState,
+        buffer: &mut [u8],
+    ) -> io::Result<bool> {
+        match self.read_exact_counted(read, &mut buffer[state.filled..state.len]) {
+            Ok(()) => {
+                state.filled = state.len;
+                Ok(true)
+            }
+            Err((copied, err)) => {
+                state.filled += copied;
+                if err.kind() == ErrorKind::WouldBlock {
+                    return Ok(false);
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Same as `read_exact` but retries when the `Read` impl returns `ErrorKind::WouldBlock` until the deadline passed.
+    /// This is intended for non-blocking sockets. The current thread sleeps briefly between retries.
+    ///
+    /// Bytes that were read before a `WouldBlock` are kept, the retry continues where the previous attempt stopped.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl, except for `ErrorKind::WouldBlock`
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    /// `ErrorKind::TimedOut` if the buffer was not filled before the deadline.
+    /// In all error cases the bytes that were read until then are consumed and can be found at the start of the buffer.
+    ///
+    #[cfg(feature = "std")]
+    pub fn read_exact_deadline<T: Read>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+        deadline: Instant,
+    ) -> io::Result<()> {
+        /// Upper bound of a single sleep between two retries.
+        const RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+        let mut count = 0usize;
+        loop {
+            match self.read_exact_counted(read, &mut buffer[count..]) {
+                Ok(()) => return Ok(()),
+                Err((copied, err)) if err.kind() == ErrorKind::WouldBlock => {
+                    count += copied;
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(io::Error::from(ErrorKind::TimedOut));
+                    }
+
+                    std::thread::sleep(RETRY_INTERVAL.min(deadline - now));
+                }
+                Err((_, err)) => return Err(err),
+            }
+        }
+    }
+
+    /// Same as `read` but additionally passes the bytes copied into the buffer to the observer.
+    /// This can be used to compute a running hash or checksum of the stream.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl, the observer is not called in this case.
+    ///
+    pub fn read_with<T: Read, F: FnMut(&[u8])>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+        mut observer: F,
+    ) -> io::Result<usize> {
+        let count = self.read(read, buffer)?;
+        if count > 0 {
+            observer(&buffer[..count]);
+        }
+
+        Ok(count)
+    }
+
+    /// Same as `read_exact` but additionally passes the bytes copied into the buffer to the observer.
+    /// This can be used to compute a running hash or checksum of the stream.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl, the observer is not called in this case.
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    ///
+    pub fn read_exact_with<T: Read, F: FnMut(&[u8])>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+        mut observer: F,
+    ) -> io::Result<()> {
+        self.read_exact(read, buffer)?;
+        if !buffer.is_empty() {
+            observer(buffer);
+        }
+
+        Ok(())
+    }
+
+    /// This fn fills the buffer from either the internal buffer or the `Read` impl until it is full or EOF occurred.
+    /// Multiple calls to the read impl may be made if necessary to fill the buffer.
+    /// Returns the amount of bytes copied into the buffer, this is only smaller than the buffer on EOF.
+    ///
+    /// Copied bytes are always consumed and can be found at the start of the buffer.
+    /// Call this fn again with the remainder of the buffer to resume after an error such as `ErrorKind::WouldBlock`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl together with the amount of bytes that were already copied into the buffer.
+    ///
+    pub fn read_fill<T: Read>(
+        &mut self,
+        read: &mut T,
+        buffer: &mut [u8],
+    ) -> Result<usize, (usize, io::Error)> {
+        let mut count = 0usize;
+        while count < buffer.len() {
+            match self.read(read, &mut buffer[count..]) {
+                Ok(0) => break,
+                Ok(read) => count += read,
+                Err(err) => return Err((count, err)),
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Reads exactly n bytes and appends them to buf.
+    /// The bytes are taken from the internal buffer first, large remainders are then read directly into buf
+    /// without going through the internal buffer. buf is never zero filled before reading.
+    ///
+    /// If EOF occurs before n bytes were read then buf is truncated to its original length.
+    /// The bytes that were read until then are lost.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before n bytes were read.
+    ///
+    pub fn read_exact_to_vec<T: Read>(
+        &mut self,
+        read: &mut T,
+        n: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let start = buf.len();
+        buf.reserve(n);
+        let result = self.read_exact_to_vec_inner(read, n, buf);
+        if result.is_err() {
+            buf.truncate(start);
+        }
+
+        result
+    }
+
+    /// Appends n bytes to buf, buf is not truncated on error.
+    fn read_exact_to_vec_inner<T: Read>(
+        &mut self,
+        read: &mut T,
+        n: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let mut remaining = n;
+        loop {
+            let count = remaining.min(self.available());
+            buf.extend_from_slice(&self.buffer.as_ref()[self.read_count..self.read_count + count]);
+            self.read_count += count;
+            remaining -= count;
+            if remaining == 0 {
+                return Ok(());
+            }
+
+            #[cfg(feature = "std")]
+            if remaining >= self.buffer.as_ref().len() {
+                //The internal buffer is empty, read the large remainder directly into buf.
+                self.read_calls += 1;
+                let count = Read::take(&mut *read, remaining as u64).read_to_end(buf)?;
+                if count != remaining {
+                    return Err(io::Error::from(ErrorKind::UnexpectedEof));
+                }
+
+                return Ok(());
+            }
+
+            if !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+        }
+    }
+
+    /// Reads exactly N bytes and returns them as an array.
+    /// This fn behaves like `read_exact`, N may be larger than the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before N bytes were read.
+    ///
+    pub fn read_array<const N: usize, T: Read>(&mut self, read: &mut T) -> io::Result<[u8; N]> {
+        let mut array = [0u8; N];
+        self.read_exact(read, &mut array)?;
+        Ok(array)
+    }
+
+    /// Returns the next N bytes as an array without consuming them.
+    /// The `Read` impl is called until at least N bytes are in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before N bytes were buffered,
+    /// all bytes that were read until then remain in the internal buffer.
+    /// `ErrorKind::InvalidInput` if N is larger than the internal buffer.
+    ///
+    pub fn peek_array<const N: usize, T: Read>(&mut self, read: &mut T) -> io::Result<[u8; N]> {
+        let buffered = self.fill_buf_at_least(read, N)?;
+        if buffered.len() < N {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+
+        let mut array = [0u8; N];
+        array.copy_from_slice(&buffered[..N]);
+        Ok(array)
+    }
+
+    /// Reads the N bytes of a fixed size integer, they are only consumed if all of them could be read.
+    fn read_int_bytes<const N: usize, T: Read>(&mut self, read: &mut T) -> io::Result<[u8; N]> {
+        //N is at most 16, so it never exceeds the internal buffer.
+        let bytes = self.peek_array(read)?;
+        self.read_count += N;
+        Ok(bytes)
+    }
+
+    for_each_int!(read_int_fns);
+
+    /// Reads a frame that consists of a big endian u32 length prefix and that many payload bytes.
+    /// The payload is appended to buf and its length is returned.
+    /// Returns None if EOF occurred before the first byte of the length prefix.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if EOF occurred within the frame, the payload bytes read until then are not appended to buf.
+    /// `ErrorKind::InvalidData` if the length is larger than `max_len`, the length prefix is not consumed in this case.
+    ///
+    pub fn read_frame<T: Read>(
+        &mut self,
+        read: &mut T,
+        max_len: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<Option<usize>> {
+        if !self.ensure_readable_at_least(read, 4)? {
+            if self.is_empty() {
+                return Ok(None);
+            }
+
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+
+        let prefix = u32::from_be_bytes(self.peek_array(read)?);
+        //The length is checked before anything is allocated.
+        let len = usize::try_from(prefix)
+            .ok()
+            .filter(|len| *len <= max_len)
+            .ok_or_else(|| {
+                io::Error::new(ErrorKind::InvalidData, "frame is larger than max_len")
+            })?;
+        self.read_count += 4;
+
+        let start = buf.len();
+        buf.resize(start + len, 0);
+        if let Err(err) = self.read_exact(read, &mut buf[start..]) {
+            buf.truncate(start);
+            return Err(err);
+        }
+
+        Ok(Some(len))
+    }
+
+    /// Reads an integer in the given byte order, for example `read_int::<u32, BigEndian>(read)`.
+    /// The bytes are only consumed if all of them could be read, on error they remain in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_int<I: EndianInt, O: ByteOrder>(&mut self, read: &mut impl Read) -> io::Result<I> {
+        let mut bytes = I::Bytes::default();
+        let len = bytes.as_ref().len();
+        //The integers are at most 16 bytes, so they never exceed the internal buffer.
+        let buffered = self.fill_buf_at_least(read, len)?;
+        if buffered.len() < len {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+
+        bytes.as_mut().copy_from_slice(&buffered[..len]);
+        self.read_count += len;
+        Ok(O::decode(bytes))
+    }
+
+    /// Reads big endian IEEE 754 bits as f32, NaN payloads are preserved. See `read_u32_be`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_f32_be<T: Read>(&mut self, read: &mut T) -> io::Result<f32> {
+        Ok(f32::from_bits(self.read_u32_be(read)?))
+    }
+
+    /// Reads little endian IEEE 754 bits as f32, NaN payloads are preserved. See `read_u32_le`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_f32_le<T: Read>(&mut self, read: &mut T) -> io::Result<f32> {
+        Ok(f32::from_bits(self.read_u32_le(read)?))
+    }
+
+    /// Reads big endian IEEE 754 bits as f64, NaN payloads are preserved. See `read_u64_be`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_f64_be<T: Read>(&mut self, read: &mut T) -> io::Result<f64> {
+        Ok(f64::from_bits(self.read_u64_be(read)?))
+    }
+
+    /// Reads little endian IEEE 754 bits as f64, NaN payloads are preserved. See `read_u64_le`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_f64_le<T: Read>(&mut self, read: &mut T) -> io::Result<f64> {
+        Ok(f64::from_bits(self.read_u64_le(read)?))
+    }
+
+    /// Reads until either EOF happens or the desired byte is found.
+    /// This fn may call the underlying `Read` impl multiple times until the buffer is filled.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_until<T: Read>(
+        &mut self,
+        read: &mut T,
+        byte: u8,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        self.read_until_counted(read, byte, buf)
+            .map_err(|(_, err)| err)
+    }
+
+    /// Same as `read_until` but on error also returns how many bytes were already appended to buf.
+    /// The internal buffer is empty in this case, all bytes read so far are in buf.
+    /// Calling this fn again with the same buf resumes the operation.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_until_counted<T: Read>(
+        &mut self,
+        read: &mut T,
+        byte: u8,
+        buf: &mut Vec<u8>,
+    ) -> Result<usize, (usize, io::Error)> {
+        let mut count: usize = 0;
+
+        if self.available() == 0 {
+            match self.feed(read) {
+                Ok(true) => {}
+                Ok(false) => return Ok(0),
+                Err(err) => return Err((0, err)),
+            }
+        }
+
+        loop {
+            let available = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            if let Some(idx) = find_byte(byte, available) {
+                let to_push = &available[..=idx];
+                buf.extend_from_slice(to_push);
+                self.read_count += to_push.len();
+                return Ok(count + to_push.len());
+            }
+
+            let to_push = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            buf.extend_from_slice(to_push);
+            count += to_push.len();
+            self.clear();
+            match self.feed(read) {
+                Ok(true) => {}
+                Ok(false) => return Ok(count),
+                Err(err) => return Err((count, err)),
+            }
+        }
+    }
+
+    /// Reads until either EOF happens or the desired byte is found or limit bytes have been appended to buf.
+    /// The actual read impl may supply more bytes than limit, the excess is stored in the internal buffer in this case.
+    /// Returns the amount of bytes appended to the buf vec.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_until_limit<T: Read>(
+        &mut self,
+        read: &mut T,
+        byte: u8,
+        limit: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        let mut count: usize = 0;
+
+        if limit == 0 {
+            return Ok(0);
+        }
+
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(0);
+        }
+
+        loop {
+            let mut to_push = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            if count + to_push.len() > limit {
+                to_push = &to_push[..limit - count];
+            }
+
+            debug_assert!(count + to_push.len() <= limit);
+
+            if let Some(idx) = find_byte(byte, to_push) {
+                to_push = &to_push[..=idx];
+                buf.extend_from_slice(to_push);
+                self.read_count += to_push.len();
+                return Ok(count + to_push.len());
+            }
+
+            buf.extend_from_slice(to_push);
+            count += to_push.len();
+            self.read_count += to_push.len();
+            if count >= limit {
+                return Ok(count);
+            }
+
+            if !self.feed(read)? {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Reads bytes as long as the predicate returns true for them and appends them to buf.
+    /// The first byte for which the predicate returns false is not consumed and remains in the internal buffer.
+    /// Reading also stops on EOF.
+    /// Returns the amount of bytes appended to the buf vec.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_while<T: Read, F: FnMut(u8) -> bool>(
+        &mut self,
+        read: &mut T,
+        pred: F,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        self.read_while_limit(read, pred, usize::MAX, buf)
+    }
+
+    /// Reads bytes as long as the predicate returns true for them and at most limit bytes have been appended to buf.
+    /// The first byte for which the predicate returns false is not consumed and remains in the internal buffer.
+    /// Reading also stops on EOF.
+    /// Returns the amount of bytes appended to the buf vec.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_while_limit<T: Read, F: FnMut(u8) -> bool>(
+        &mut self,
+        read: &mut T,
+        mut pred: F,
+        limit: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        let mut count = 0usize;
+        while count < limit {
+            if self.available() == 0 && !self.feed(read)? {
+                return Ok(count);
+            }
+
+            let chunk = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            let chunk = &chunk[..chunk.len().min(limit - count)];
+            let matched = chunk.iter().position(|b| !pred(*b));
+            let to_push = &chunk[..matched.unwrap_or(chunk.len())];
+            buf.extend_from_slice(to_push);
+            count += to_push.len();
+            self.read_count += to_push.len();
+            if matched.is_some() {
+                return Ok(count);
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Reads until a NUL (0x00) byte is found. The NUL byte is consumed but not appended to buf.
+    /// This fn may call the underlying `Read` impl multiple times until the NUL byte is found.
+    /// Returns the amount of bytes appended to buf.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the NUL byte was found.
+    /// All bytes read until then have been appended to buf.
+    ///
+    pub fn read_cstr<T: Read>(&mut self, read: &mut T, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut count = 0usize;
+        loop {
+            if self.available() == 0 && !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+
+            let to_push = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            if let Some(idx) = find_byte(0, to_push) {
+                buf.extend_from_slice(&to_push[..idx]);
+                self.read_count += idx + 1;
+                return Ok(count + idx);
+            }
+
+            buf.extend_from_slice(to_push);
+            count += to_push.len();
+            self.clear();
+        }
+    }
+
+    /// Reads until a NUL (0x00) byte is found, EOF occurred or limit bytes have been appended to buf.
+    /// The NUL byte is consumed but not appended to buf and does not count towards the limit.
+    /// If the limit is reached the byte after the last appended byte remains in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_nul_terminated<T: Read>(
+        &mut self,
+        read: &mut T,
+        limit: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<NulReadOutcome> {
+        let mut count = 0usize;
+        loop {
+            if self.available() == 0 && !self.feed(read)? {
+                return Ok(NulReadOutcome::Eof(count));
+            }
+
+            let remaining = limit - count;
+            let chunk = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            //One byte past the limit is inspected because the NUL byte does not count towards it.
+            let window = &chunk[..chunk.len().min(remaining.saturating_add(1))];
+            if let Some(idx) = find_byte(0, window) {
+                buf.extend_from_slice(&window[..idx]);
+                self.read_count += idx + 1;
+                return Ok(NulReadOutcome::Terminated(count + idx));
+            }
+
+            let to_push = &window[..window.len().min(remaining)];
+            buf.extend_from_slice(to_push);
+            count += to_push.len();
+            self.read_count += to_push.len();
+            if window.len() > remaining {
+                return Ok(NulReadOutcome::LimitReached);
+            }
+        }
+    }
+
+    /// Reads a NUL terminated string of at most limit bytes, excluding the NUL byte.
+    /// See `read_nul_terminated` for details.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if EOF occurred before the NUL byte was found.
+    /// `ErrorKind::InvalidData` if no NUL byte was found within limit bytes.
+    /// The bytes read until then are lost in both cases.
+    ///
+    pub fn read_cstring<T: Read>(&mut self, read: &mut T, limit: usize) -> io::Result<CString> {
+        let mut buf = Vec::new();
+        match self.read_nul_terminated(read, limit, &mut buf)? {
+            NulReadOutcome::Terminated(_) => CString::new(buf).map_err(|_| {
+                io::Error::new(ErrorKind::InvalidData, "c string contains an interior NUL")
+            }),
+            NulReadOutcome::LimitReached => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "c string is longer than the limit",
+            )),
+            NulReadOutcome::Eof(_) => Err(io::Error::from(ErrorKind::UnexpectedEof)),
+        }
+    }
+
+    /// Reads all remaining bytes into the buffer.
+    /// Those bytes may be from the internal buffer and then from the underlying `Read` impl.
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_to_end<T: Read>(&mut self, read: &mut T, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let buffered = self.available();
+        buf.extend_from_slice(&self.buffer.as_ref()[self.read_count..self.fill_count]);
+        self.clear();
+        #[cfg(feature = "std")]
+        {
+            //The internal buffer is empty, the Read impl knows best how to read the remainder.
+            self.read_calls += 1;
+            Ok(buffered + read.read_to_end(buf)?)
+        }
+
+        #[cfg(not(feature = "std"))]
+        {
+            //The read_to_end of the io shim only reads small chunks, the internal buffer is usually larger.
+            let mut count = buffered;
+            while self.feed(read)? {
+                count += self.available();
+                buf.extend_from_slice(&self.buffer.as_ref()[self.read_count..self.fill_count]);
+                self.clear();
+            }
+
+            Ok(count)
+        }
+    }
+
+    /// Same as `read_to_end` but on error also returns how many bytes were already appended to buf.
+    /// The internal buffer is empty in this case, all bytes read so far are in buf.
+    /// Calling this fn again with the same buf resumes the operation.
+    /// Unlike `read_to_end` all bytes pass through the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_to_end_counted<T: Read>(
+        &mut self,
+        read: &mut T,
+        buf: &mut Vec<u8>,
+    ) -> Result<usize, (usize, io::Error)> {
+        let mut count = 0usize;
+        loop {
+            count += self.available();
+            buf.extend_from_slice(&self.buffer.as_ref()[self.read_count..self.fill_count]);
+            self.clear();
+            match self.feed(read) {
+                Ok(true) => {}
+                Ok(false) => return Ok(count),
+                Err(err) => return Err((count, err)),
+            }
+        }
+    }
+
+    /// Same as `read_to_end` but appends at most limit bytes to buf.
+    /// If the stream is longer then the `Read` impl may have supplied more bytes than limit,
+    /// the excess is stored in the internal buffer and returned by subsequent reads.
+    /// Returns the amount of bytes appended to buf, this is limit unless EOF occurred before.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_to_end_limit<T: Read>(
+        &mut self,
+        read: &mut T,
+        limit: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        let mut count = 0usize;
+        loop {
+            let to_push = self.available().min(limit - count);
+            buf.extend_from_slice(
+                &self.buffer.as_ref()[self.read_count..self.read_count + to_push],
+            );
+            self.read_count += to_push;
+            count += to_push;
+            if count == limit || !self.feed(read)? {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Same as `read_to_end` but reserves `size_hint` bytes in buf first.
+    /// The hint only avoids reallocations, streams that are shorter or longer than the hint are read completely.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_to_end_with_hint<T: Read>(
+        &mut self,
+        read: &mut T,
+        size_hint: usize,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        buf.reserve(size_hint);
+        self.read_to_end(read, buf)
+    }
+
+    /// Reads all remaining bytes into the String.
+    /// Those bytes may be from the internal buffer and then from the underlying `Read` impl.
+    /// If the `Read` or buffer contained non-valid utf-8 sequences then this fn returns an `io::Error` with Kind `InvalidData`.
+    /// No data is lost in this case as bytes read and already placed in the buf are, in the buf and all remaining bytes
+    /// starting with those that were not valid utf-8 are in the internal buffer and can be fetched with a call to `try_read()`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. The error carries an `InvalidUtf8Error`.
+    ///
+    pub fn read_to_string<T: Read>(&mut self, read: &mut T, buf: &mut String) -> io::Result<usize> {
+        self.read_to_string_counted(read, buf)
+            .map_err(|(_, err)| err)
+    }
+
+    /// Same as `read_to_string` but on error also returns how many bytes were already appended to buf.
+    /// If the `Read` impl failed then the internal buffer only contains the start of a multibyte sequence
+    /// that was not completed yet, at most 3 bytes. Calling this fn again with the same buf resumes the operation.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. The error carries an `InvalidUtf8Error`.
+    ///
+    pub fn read_to_string_counted<T: Read>(
+        &mut self,
+        read: &mut T,
+        buf: &mut String,
+    ) -> Result<usize, (usize, io::Error)> {
+        let mut count = 0usize;
+        if self.available() == 0 {
+            match self.feed(read) {
+                Ok(true) => {}
+                Ok(false) => return Ok(0),
+                Err(err) => return Err((0, err)),
+            }
+        }
+
+        loop {
+            let to_push = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            //An incomplete multibyte sequence at the end is left in the buffer for the next cycle.
+            let valid = valid_utf8_prefix(to_push).map_err(|_| (count, self.utf8_error(count)))?;
+            buf.push_str(valid);
+            count += valid.len();
+            self.read_count += valid.len(); //feed will compact the buffer.
+
+            match self.feed(read) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(err) => return Err((count, err)),
+            }
+
+            if self.available() > 0 {
+                //EOF in the middle of a multibyte sequence.
+                return Err((count, self.utf8_error(count)));
+            }
+
+            return Ok(count);
+        }
+    }
+
+    /// Reads all remaining bytes and passes them to the callback in chunks of valid utf-8.
+    /// Unlike `read_to_string` this fn never allocates, each chunk is borrowed from the internal buffer.
+    /// Incomplete multibyte sequences at the end of a chunk are carried over to the next chunk.
+    /// Returns the total amount of bytes passed to the callback.
+    ///
+    /// A chunk is only consumed if the callback returned Ok, the chunk passed to a failing callback remains in the internal buffer.
+    /// Invalid utf-8 is handled like in `read_to_string`, all bytes starting with the invalid ones remain in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// Propagated from the callback
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. The error carries an `InvalidUtf8Error`.
+    ///
+    pub fn read_to_string_chunks<T: Read, F: FnMut(&str) -> io::Result<()>>(
+        &mut self,
+        read: &mut T,
+        mut f: F,
+    ) -> io::Result<u64> {
+        let mut count = 0u64;
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(0);
+        }
+
+        loop {
+            let chunk = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            //An incomplete multibyte sequence at the end is left in the buffer for the next cycle.
+            let valid = valid_utf8_prefix(chunk)
+                .map_err(|_| self.utf8_error(usize::try_from(count).unwrap_or(usize::MAX)))?;
+            if !valid.is_empty() {
+                f(valid)?;
+                count += valid.len() as u64;
+                self.read_count += valid.len(); //feed will compact the buffer.
+            }
+
+            if self.feed(read)? {
+                continue;
+            }
+
+            if self.available() > 0 {
+                //EOF in the middle of a multibyte sequence.
+                return Err(self.utf8_error(usize::try_from(count).unwrap_or(usize::MAX)));
+            }
+
+            return Ok(count);
+        }
+    }
+
+    ///
+    /// Reads all bytes into the string until \n is found, or EOF occurred.
+    /// Data is first taken from the internal buffer and then taken from the `Read` impl.
+    ///
+    /// # Major difference from `BufRead`'s `read_line` fn:
+    /// this fn guarantees that no data is discarded when invalid utf-8 is encountered.
+    /// buf may contain some valid bytes in this case but all invalid bytes are retained in the internal buffer
+    /// so that the next call to `read()` can pick them up. call `available()` so you know how many bytes to read!
+    /// `BufRead`'s `read_line` fn just discards the last chunk of invalid data.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. The error carries an `InvalidUtf8Error`.
+    ///
+    pub fn read_line<T: Read>(&mut self, read: &mut T, buf: &mut String) -> io::Result<usize> {
+        self.read_line_limit(read, usize::MAX, buf)
+    }
+
+    /// Same as `read_line` but appends at most limit bytes to the String.
+    /// If no \n is found within limit bytes then the rest of the line remains in the internal buffer
+    /// and is returned by the next call. Fewer than limit bytes may be appended if the limit would split a character.
+    /// Use this to guard against peers that send huge amounts of data without a \n.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. The error carries an `InvalidUtf8Error`.
+    /// `ErrorKind::InvalidInput` if bytes follow but limit is too small to hold the next character, this includes a limit of 0.
+    /// Nothing is consumed in this case.
+    ///
+    pub fn read_line_limit<T: Read>(
+        &mut self,
+        read: &mut T,
+        limit: usize,
+        buf: &mut String,
+    ) -> io::Result<usize> {
+        let mut count = 0usize;
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(0);
+        }
+
+        loop {
+            let chunk = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            let remaining = limit - count;
+            let to_push = &chunk[..chunk.len().min(remaining)];
+            if let Some(idx) = find_byte(b'\n', to_push) {
+                //We found it! \n is never part of a multibyte sequence, so everything up to it must be valid.
+                let to_push = &to_push[..=idx];
+                buf.push_str(read_utf8(to_push).map_err(|_| self.utf8_error(count))?);
+                self.read_count += to_push.len();
+                return Ok(count + to_push.len());
+            }
+
+            let limited = chunk.len() >= remaining;
+            //An incomplete multibyte sequence at the end is left in the buffer for the next cycle.
+            let valid = valid_utf8_prefix(to_push).map_err(|_| self.utf8_error(count))?;
+            buf.push_str(valid);
+            count += valid.len();
+            self.read_count += valid.len();
+            if limited {
+                if count == 0 {
+                    return Err(limit_too_small_error());
+                }
+
+                return Ok(count);
+            }
+
+            if !self.feed(read)? {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Skips leading ASCII whitespace (space, \t, \r, \n) and then reads all bytes until the next ASCII whitespace
+    /// or EOF into the String. The whitespace that ends the token is not consumed.
+    /// Returns the amount of bytes appended to the String, 0 means that EOF occurred before a token was found.
+    ///
+    /// Invalid utf-8 is handled like in `read_line`, all bytes starting with the invalid ones remain in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. The error carries an `InvalidUtf8Error`.
+    ///
+    pub fn read_token<T: Read>(&mut self, read: &mut T, buf: &mut String) -> io::Result<usize> {
+        self.read_token_limit(read, usize::MAX, buf)
+    }
+
+    /// Same as `read_token` but appends at most limit bytes to the String.
+    /// If the token is longer than limit then the rest of the token remains in the internal buffer
+    /// and is returned by the next call. Fewer than limit bytes may be appended if the limit would split a character.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. The error carries an `InvalidUtf8Error`.
+    /// `ErrorKind::InvalidInput` if a token follows but limit is too small to hold its next character, this includes a limit of 0.
+    /// Only the leading whitespace is consumed in this case.
+    ///
+    pub fn read_token_limit<T: Read>(
+        &mut self,
+        read: &mut T,
+        limit: usize,
+        buf: &mut String,
+    ) -> io::Result<usize> {
+        loop {
+            if self.available() == 0 && !self.feed(read)? {
+                return Ok(0);
+            }
+
+            let chunk = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            let whitespace = chunk
+                .iter()
+                .take_while(|b| is_token_whitespace(**b))
+                .count();
+            self.read_count += whitespace;
+            if whitespace < chunk.len() {
+                break;
+            }
+        }
+
+        let mut count = 0usize;
+        loop {
+            let chunk = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            let end = chunk.iter().position(|b| is_token_whitespace(*b));
+            let token = &chunk[..end.unwrap_or(chunk.len())];
+            let remaining = limit - count;
+            if end.is_some() && token.len() <= remaining {
+                //\t, \r, \n and space are never part of a multibyte sequence, so the token must be valid.
+                let valid = read_utf8(token).map_err(|_| self.utf8_error(count))?;
+                buf.push_str(valid);
+                self.read_count += valid.len();
+                return Ok(count + valid.len());
+            }
+
+            let limited = token.len() >= remaining;
+            //An incomplete multibyte sequence at the end is left in the buffer for the next cycle.
+            let valid = valid_utf8_prefix(&token[..token.len().min(remaining)])
+                .map_err(|_| self.utf8_error(count))?;
+            buf.push_str(valid);
+            count += valid.len();
+            self.read_count += valid.len();
+            if limited {
+                if count == 0 {
+                    return Err(limit_too_small_error());
+                }
+
+                return Ok(count);
+            }
+
+            if !self.feed(read)? {
+                if self.available() > 0 {
+                    //EOF in the middle of a multibyte sequence.
+                    return Err(self.utf8_error(count));
+                }
+
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Reads all remaining bytes and decodes them into the String using the given encoding.
+    /// Those bytes may be from the internal buffer and then from the underlying `Read` impl.
+    /// Multibyte sequences that are split between two calls to the `Read` impl are decoded correctly.
+    /// Invalid sequences are replaced by the decoder with U+FFFD REPLACEMENT CHARACTER.
+    /// No BOM sniffing is done, the bytes are always decoded with the given encoding.
+    ///
+    /// Returns the amount of bytes consumed, which is usually not the amount of bytes appended to buf.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    #[cfg(feature = "encoding")]
+    pub fn read_to_string_encoded<T: Read>(
+        &mut self,
+        read: &mut T,
+        encoding: &'static encoding_rs::Encoding,
+        buf: &mut String,
+    ) -> io::Result<usize> {
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+        let mut count = 0usize;
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(0);
+        }
+
+        loop {
+            let to_decode = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            decode_to_string(&mut decoder, to_decode, buf, false);
+            count += to_decode.len();
+            //The decoder retains incomplete multibyte sequences, so the buffer is empty now.
+            self.clear();
+
+            if !self.feed(read)? {
+                decode_to_string(&mut decoder, &[], buf, true);
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Reads all bytes until \n is found, or EOF occurred and decodes them into the String using the given encoding.
+    /// Data is first taken from the internal buffer and then taken from the `Read` impl.
+    /// Multibyte sequences that are split between two calls to the `Read` impl are decoded correctly.
+    /// Invalid sequences are replaced by the decoder with U+FFFD REPLACEMENT CHARACTER.
+    /// No BOM sniffing is done, the bytes are always decoded with the given encoding.
+    ///
+    /// For encodings that are not ascii compatible (for example UTF-16) the \n may be encoded in more than one byte.
+    /// Those encodings are decoded byte by byte so that no byte after the \n is consumed, which is slower.
+    ///
+    /// Returns the amount of bytes consumed, which is usually not the amount of bytes appended to buf.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    #[cfg(feature = "encoding")]
+    pub fn read_line_encoded<T: Read>(
+        &mut self,
+        read: &mut T,
+        encoding: &'static encoding_rs::Encoding,
+        buf: &mut String,
+    ) -> io::Result<usize> {
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+        let mut count = 0usize;
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(0);
+        }
+
+        loop {
+            let to_decode = &self.buffer.as_ref()[self.read_count..self.fill_count];
+            if encoding.is_ascii_compatible() {
+                //\n can never be part of a multibyte sequence here, the decoder has nothing pending after it.
+                if let Some(idx) = find_byte(b'\n', to_decode) {
+                    decode_to_string(&mut decoder, &to_decode[..=idx], buf, false);
+                    self.read_count += idx + 1;
+                    return Ok(count + idx + 1);
+                }
+
+                decode_to_string(&mut decoder, to_decode, buf, false);
+            } else {
+                for idx in 0..to_decode.len() {
+                    let len = buf.len();
+                    decode_to_string(&mut decoder, &to_decode[idx..=idx], buf, false);
+                    if buf.len() > len && buf.ends_with('\n') {
+                        self.read_count += idx + 1;
+                        return Ok(count + idx + 1);
+                    }
+                }
+            }
+
+            count += to_decode.len();
+            self.clear();
+
+            if !self.feed(read)? {
+                decode_to_string(&mut decoder, &[], buf, true);
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Reads an unsigned LEB128 varint.
+    /// This fn may call the underlying `Read` impl multiple times until the entire varint is buffered.
+    /// The bytes of the varint are only consumed if the varint was read successfully,
+    /// in case of an error they remain in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 10 bytes or does not fit into an u64.
+    ///
+    pub fn read_varint_u64<T: Read>(&mut self, read: &mut T) -> io::Result<u64> {
+        let mut value = 0u64;
+        for idx in 0..10 {
+            //The buffer is at least 16 bytes, so there is always room for the 10 bytes after feed compacted the buffer.
+            if idx >= self.available() && !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+
+            let byte = self.buffer.as_ref()[self.read_count + idx];
+            if idx == 9 && byte > 1 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "varint does not fit into u64",
+                ));
+            }
+
+            value |= u64::from(byte & 0x7F) << (idx * 7);
+            if byte & 0x80 == 0 {
+                self.read_count += idx + 1;
+                return Ok(value);
+            }
+        }
+
+        //The 10th byte either terminates the varint or is rejected above.
+        unreachable!()
+    }
+
+    /// Reads an unsigned LEB128 varint of at most 5 bytes.
+    /// The bytes of the varint are only consumed if the varint was read successfully,
+    /// in case of an error they remain in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 5 bytes or does not fit into an u32.
+    ///
+    pub fn read_varint_u32<T: Read>(&mut self, read: &mut T) -> io::Result<u32> {
+        let mut value = 0u32;
+        for idx in 0..5 {
+            if idx >= self.available() && !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+
+            let byte = self.buffer.as_ref()[self.read_count + idx];
+            //Only 4 bits are left for the 5th byte.
+            if idx == 4 && byte > 0x0F {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "varint does not fit into u32",
+                ));
+            }
+
+            value |= u32::from(byte & 0x7F) << (idx * 7);
+            if byte & 0x80 == 0 {
+                self.read_count += idx + 1;
+                return Ok(value);
+            }
+        }
+
+        //The 5th byte either terminates the varint or is rejected above.
+        unreachable!()
+    }
+
+    /// Reads a zigzag encoded signed LEB128 varint, this is the encoding protobuf uses for sint64.
+    /// The bytes of the varint are only consumed if the varint was read successfully.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 10 bytes or does not fit into an i64.
+    ///
+    pub fn read_varint_i64<T: Read>(&mut self, read: &mut T) -> io::Result<i64> {
+        Ok(zigzag_decode(self.read_varint_u64(read)?))
+    }
+
+    /// Reads a zigzag encoded signed LEB128 varint, this is the encoding protobuf uses for sint32.
+    /// The bytes of the varint are only consumed if the varint was read successfully.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 5 bytes or does not fit into an i32.
+    ///
+    pub fn read_varint_i32<T: Read>(&mut self, read: &mut T) -> io::Result<i32> {
+        Ok(zigzag_decode_32(self.read_varint_u32(read)?))
+    }
+
+    /// Reads an ASCII decimal number such as "  +1234".
+    /// Leading ASCII whitespace (space, \t, \r, \n) and an optional '+' sign are skipped.
+    /// Reading stops at the first byte that is not a digit, this byte is not consumed.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if EOF occurred before anything but whitespace was read.
+    /// `ErrorKind::InvalidData` if there are no digits, the number is negative or does not fit into u64.
+    /// The whitespace, the sign and in case of overflow all digits are consumed.
+    ///
+    pub fn read_ascii_u64<T: Read>(&mut self, read: &mut T) -> io::Result<u64> {
+        Ok(self.read_ascii_number(read, false)?.1)
+    }
+
+    /// Reads an ASCII decimal number such as "  -1234".
+    /// Leading ASCII whitespace (space, \t, \r, \n) and an optional '+' or '-' sign are skipped.
+    /// Reading stops at the first byte that is not a digit, this byte is not consumed.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if EOF occurred before anything but whitespace was read.
+    /// `ErrorKind::InvalidData` if there are no digits or the number does not fit into i64.
+    /// The whitespace, the sign and in case of overflow all digits are consumed.
+    ///
+    #[allow(clippy::cast_possible_wrap)] //The magnitude was checked against the i64 range.
+    pub fn read_ascii_i64<T: Read>(&mut self, read: &mut T) -> io::Result<i64> {
+        let (negative, magnitude) = self.read_ascii_number(read, true)?;
+        if negative {
+            //The magnitude is at most 2^63 which wraps to i64::MIN.
+            return Ok((magnitude as i64).wrapping_neg());
+        }
+
+        Ok(magnitude as i64)
+    }
+
+    /// Reads an ASCII decimal number and returns whether it is negative and its magnitude.
+    /// The magnitude of negative numbers is at most 2^63, positive numbers are limited to `i64::MAX` if signed.
+    fn read_ascii_number<T: Read>(
+        &mut self,
+        read: &mut T,
+        signed: bool,
+    ) -> io::Result<(bool, u64)> {
+        let mut byte = loop {
+            if self.available() == 0 && !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+
+            let byte = self.buffer.as_ref()[self.read_count];
+            if !is_token_whitespace(byte) {
+                break byte;
+            }
+
+            self.read_count += 1;
+        };
+
+        let negative = byte == b'-';
+        if negative && !signed {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "negative number cannot be read as unsigned",
+            ));
+        }
+
+        if negative || byte == b'+' {
+            self.read_count += 1;
+            //EOF after the sign is reported as a number without digits.
+            byte = self.peek_u8(read)?.unwrap_or_default();
+        }
+
+        let max = match (signed, negative) {
+            (false, _) => u64::MAX,
+            (true, false) => i64::MAX.unsigned_abs(),
+            (true, true) => i64::MIN.unsigned_abs(),
+        };
+
+        if !byte.is_ascii_digit() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "number has no digits",
+            ));
+        }
+
+        let mut value = 0u64;
+        while let Some(byte) = self.peek_u8(read)? {
+            if !byte.is_ascii_digit() {
+                break;
+            }
+
+            self.read_count += 1;
+            let Some(next) = value
+                .checked_mul(10)
+                .and_then(|value| value.checked_add(u64::from(byte - b'0')))
+                .filter(|value| *value <= max)
+            else {
+                //Consume the remaining digits so the number is skipped entirely.
+                while self
+                    .peek_u8(read)?
+                    .is_some_and(|byte| byte.is_ascii_digit())
+                {
+                    self.read_count += 1;
+                }
+
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "number is too large",
+                ));
+            };
+            value = next;
+        }
+
+        Ok((negative, value))
+    }
+
+    /// Returns the next byte without consuming it or None on EOF.
+    fn peek_u8<T: Read>(&mut self, read: &mut T) -> io::Result<Option<u8>> {
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(None);
+        }
+
+        Ok(Some(self.buffer.as_ref()[self.read_count]))
+    }
+
+    /// Reads a single utf-8 character.
+    /// This fn may call the underlying `Read` impl multiple times until the entire character is buffered.
+    /// Returns None if EOF occurred before the first byte of the character.
+    /// If the bytes are not valid utf-8 then they are not consumed and remain in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found.
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the character is complete.
+    ///
+    pub fn read_char<T: Read>(&mut self, read: &mut T) -> io::Result<Option<char>> {
+        let Some((char, len)) = self.next_char(read)? else {
+            return Ok(None);
+        };
+
+        self.read_count += len;
+        Ok(Some(char))
+    }
+
+    /// Same as `read_char` but the character is not consumed and remains in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found.
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the character is complete.
+    ///
+    pub fn peek_char<T: Read>(&mut self, read: &mut T) -> io::Result<Option<char>> {
+        Ok(self.next_char(read)?.map(|(char, _)| char))
+    }
+
+    /// Decodes the next utf-8 character without consuming it. Returns the character and its length in bytes.
+    fn next_char<T: Read>(&mut self, read: &mut T) -> io::Result<Option<(char, usize)>> {
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(None);
+        }
+
+        let len = utf8_len(self.buffer.as_ref()[self.read_count]);
+        if len == 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "stream did not contain valid utf-8",
+            ));
+        }
+
+        //The buffer is at least 16 bytes, so there is always room for the 4 bytes after feed compacted the buffer.
+        for idx in 1..len {
+            if idx >= self.available() && !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+
+            utf8_cont_assert(self.buffer.as_ref()[self.read_count + idx])?;
+        }
+
+        Ok(
+            read_utf8(&self.buffer.as_ref()[self.read_count..self.read_count + len])?
+                .chars()
+                .next()
+                .map(|char| (char, len)),
+        )
+    }
+
+    /// `ReadBuf`'s fill buf equivalent. This will only pull data from the underlying read if the internal buffer is empty.
+    /// # Errors
+    /// Propagated from the `Read` impl
+    pub fn fill_buf<T: Read>(&mut self, read: &mut T) -> io::Result<&[u8]> {
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(&[]);
+        }
+
+        Ok(&self.buffer.as_ref()[self.read_count..self.fill_count])
+    }
+
+    /// Same as `fill_buf` but the `Read` impl is offered at most max bytes of the internal buffer.
+    /// This prevents reading past a boundary, for example the end of a frame that belongs to another decoder.
+    /// # Errors
+    /// Propagated from the `Read` impl
+    pub fn fill_buf_limited<T: Read>(&mut self, read: &mut T, max: usize) -> io::Result<&[u8]> {
+        if self.available() == 0 && (max == 0 || !self.feed_limited(read, max)?) {
+            return Ok(&[]);
+        }
+
+        Ok(&self.buffer.as_ref()[self.read_count..self.fill_count])
+    }
+
+    /// Calls the `Read` impl once to append to the internal buffer and returns the amount of appended bytes.
+    /// The internal buffer is compacted first if little space is left at its end.
+    /// The `Read` impl is not called if the internal buffer is full, Ok(0) is returned in this case.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn try_read_into_internal_buffer<T: Read>(&mut self, read: &mut T) -> io::Result<usize> {
+        self.read_into_internal_buffer_limit(read, usize::MAX)
+    }
+
+    /// Calls the `Read` impl once to append at most max bytes to the internal buffer and returns the amount of appended bytes.
+    /// The internal buffer is compacted first if little space is left at its end.
+    /// The `Read` impl is not called if max is 0 or the internal buffer is full, Ok(0) is returned in this case.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_into_internal_buffer_limit<T: Read>(
+        &mut self,
+        read: &mut T,
+        max: usize,
+    ) -> io::Result<usize> {
+        if max == 0 || self.total_free() == 0 {
+            return Ok(0);
+        }
+
+        let start = self.available();
+        self.feed_limited(read, max)?;
+        Ok(self.available() - start)
+    }
+
+    /// Same as `fill_buf` but the returned slice is mutable.
+    /// Changes made to the slice are seen by all following reads, this allows decoding bytes in place.
+    /// In general, it should be paired with calls to `consume`.
+    /// # Errors
+    /// Propagated from the `Read` impl
+    pub fn fill_buf_mut<T: Read>(&mut self, read: &mut T) -> io::Result<&mut [u8]> {
+        if self.available() == 0 && !self.feed(read)? {
+            return Ok(&mut []);
+        }
+
+        Ok(&mut self.buffer.as_mut()[self.read_count..self.fill_count])
+    }
+
+    /// Reads exactly n bytes and returns them as a slice of the internal buffer instead of copying them.
+    /// The `Read` impl is called until n bytes are in the internal buffer, then they are consumed.
+    ///
+    /// The returned slice borrows this buffer mutably, so it cannot outlive the next call to any fn of this buffer.
+    /// The consumed bytes are only overwritten by a later read from the `Read` impl.
+    /// ```compile_fail
+    /// let mut buf = unowned_buf::UnownedReadBuffer::<16>::new();
+    /// let mut read: &[u8] = b"0123456789";
+    /// let header = buf.read_exact_view(&mut read, 4).unwrap();
+    /// let body = buf.read_exact_view(&mut read, 4).unwrap();
+    /// assert_eq!(header, body);
+    /// ```
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before n bytes were buffered,
+    /// all bytes that were read until then remain in the internal buffer.
+    /// `ErrorKind::InvalidInput` if n is larger than the internal buffer.
+    ///
+    pub fn read_exact_view<T: Read>(&mut self, read: &mut T, n: usize) -> io::Result<&[u8]> {
+        if self.fill_buf_at_least(read, n)?.len() < n {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+
+        let start = self.read_count;
+        self.read_count += n;
+        Ok(&self.buffer.as_ref()[start..start + n])
+    }
+
+    /// Compacts the internal buffer and calls the `Read` impl once to append to it, even if it still contains unread bytes.
+    /// Returns all buffered bytes. The `Read` impl is not called if the internal buffer is full.
+    /// An unchanged slice is returned if the `Read` impl returned EOF.
+    /// Compacting invalidates all marks.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn fill_buf_more<T: Read>(&mut self, read: &mut T) -> io::Result<&[u8]> {
+        self.compact();
+        if self.available_space() > 0 {
+            self.feed(read)?;
+        }
+
+        Ok(&self.buffer.as_ref()[self.read_count..self.fill_count])
+    }
+
+    /// Compacts the internal buffer and calls the `Read` impl until the internal buffer is full or EOF occurred.
+    /// Returns the amount of newly buffered bytes.
+    /// Compacting invalidates all marks.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl, including `WouldBlock`.
+    /// Bytes that were read before the error remain in the internal buffer.
+    ///
+    pub fn fill_to_capacity<T: Read>(&mut self, read: &mut T) -> io::Result<usize> {
+        self.compact();
+        let start = self.fill_count;
+        while self.available_space() > 0 {
+            if !self.feed(read)? {
+                break;
+            }
+        }
+
+        Ok(self.fill_count - start)
+    }
+
+    /// Calls the `Read` impl until at least min bytes are in the internal buffer and returns all buffered bytes.
+    /// Fewer than min bytes are only returned if EOF occurred.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidInput` if min is larger than the internal buffer.
+    ///
+    pub fn fill_buf_at_least<T: Read>(&mut self, read: &mut T, min: usize) -> io::Result<&[u8]> {
+        if min > self.buffer.as_ref().len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "min is larger than the internal buffer",
+            ));
+        }
+
+        while self.available() < min {
+            if !self.feed(read)? {
+                break;
+            }
+        }
+
+        Ok(&self.buffer.as_ref()[self.read_count..self.fill_count])
+    }
+
+    /// Calls the `Read` impl until byte is found in the internal buffer and returns its offset relative to the next unread byte.
+    /// Nothing is consumed. Only the first max bytes, at most the size of the internal buffer, are searched.
+    /// Returns None if byte was not found within those bytes or if EOF occurred before.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl, all bytes that were read until then remain in the internal buffer.
+    ///
+    pub fn peek_until<T: Read>(
+        &mut self,
+        read: &mut T,
         byte: u8,
-        limit: usize,
-        buf: &mut Vec<u8>,
+        max: usize,
+    ) -> io::Result<Option<usize>> {
+        let limit = max.min(self.buffer.as_ref().len());
+        let mut searched = 0usize;
+        loop {
+            let window = &self.internal_buffer()[..self.available().min(limit)];
+            if let Some(index) = find_byte(byte, &window[searched..]) {
+                return Ok(Some(searched + index));
+            }
+
+            searched = window.len();
+            if searched >= limit || !self.feed(read)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// `ReadBuf`'s consume fn.
+    /// In general, it should be paired with calls to `fill_buf`
+    /// # Panics
+    /// This function will panic if amt is > available
+    ///
+    pub fn consume(&mut self, amt: usize) {
+        assert!(self.read_count + amt <= self.fill_count);
+        self.read_count += amt;
+    }
+
+    /// Same as `consume` but returns the amount of available bytes instead of panicking if amt is larger than that.
+    /// Nothing is consumed in this case.
+    ///
+    /// # Errors
+    /// the amount of available bytes if amt is larger than that.
+    ///
+    pub const fn try_consume(&mut self, amt: usize) -> Result<(), usize> {
+        let available = self.available();
+        if amt > available {
+            return Err(available);
+        }
+
+        self.read_count += amt;
+        Ok(())
+    }
+
+    /// Consumes at most amt bytes of the internal buffer and returns how many were consumed.
+    pub fn consume_up_to(&mut self, amt: usize) -> usize {
+        let count = amt.min(self.available());
+        self.read_count += count;
+        count
+    }
+
+    /// Inverse of `consume`, makes the last amt read bytes of the internal buffer available again.
+    /// This only works for bytes that are still in the internal buffer.
+    /// Any call to the `Read` impl may compact the internal buffer, which discards all bytes that were already read.
+    /// Use `mark` and `reset` to find out whether that happened.
+    /// # Panics
+    /// This function will panic if amt is larger than the amount of already read bytes in the internal buffer.
+    ///
+    pub fn unconsume(&mut self, amt: usize) {
+        assert!(amt <= self.read_count);
+        self.read_count -= amt;
+    }
+
+    /// Discards exactly amount bytes, first from the internal buffer and then from the `Read` impl.
+    /// Multiple calls to the read impl may be made if necessary.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before amount bytes were discarded.
+    ///
+    pub fn skip_exact<T: Read>(&mut self, read: &mut T, amount: usize) -> io::Result<()> {
+        let mut remaining = amount;
+        loop {
+            let count = remaining.min(self.available());
+            self.read_count += count;
+            remaining -= count;
+            if remaining == 0 {
+                return Ok(());
+            }
+
+            if !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+        }
+    }
+
+    /// Reads exactly `expected.len()` bytes and returns true if they are equal to expected.
+    /// The bytes are consumed even if they do not match. Useful to check magic numbers at the start of a file.
+    /// Multiple calls to the read impl may be made if necessary.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before `expected.len()` bytes were read.
+    ///
+    pub fn expect_bytes<T: Read>(&mut self, read: &mut T, expected: &[u8]) -> io::Result<bool> {
+        let mut remaining = expected;
+        let mut matches = true;
+        loop {
+            let count = remaining.len().min(self.available());
+            let start = self.read_count;
+            matches &= self.buffer.as_ref()[start..start + count] == remaining[..count];
+            self.read_count += count;
+            remaining = &remaining[count..];
+            if remaining.is_empty() {
+                return Ok(matches);
+            }
+
+            if !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+        }
+    }
+
+    /// Returns a mark of the current read position that `reset` can later rewind to.
+    ///
+    /// The mark stays valid as long as the already read bytes remain in the internal buffer.
+    /// Pulling more data from the `Read` impl may move or discard those bytes, which invalidates all marks.
+    /// Reading at most `available()` bytes after taking the mark never invalidates it.
+    #[must_use]
+    pub const fn mark(&self) -> Mark {
+        Mark {
+            read_count: self.read_count,
+            generation: self.generation,
+        }
+    }
+
+    /// Rewinds the read position to the given mark so that all bytes read since then are read again.
+    /// The mark must have been obtained from this buffer.
+    ///
+    /// # Errors
+    /// `MarkInvalidated` if the bytes read since the mark are no longer in the internal buffer.
+    /// The read position is not changed in this case.
+    ///
+    pub const fn reset(&mut self, mark: Mark) -> Result<(), MarkInvalidated> {
+        if mark.generation != self.generation || mark.read_count > self.read_count {
+            return Err(MarkInvalidated);
+        }
+
+        self.read_count = mark.read_count;
+        Ok(())
+    }
+
+    /// Returns the bytes in the internal buffer that have not been read yet.
+    /// This fn never calls the underlying `Read` impl.
+    #[must_use]
+    pub fn internal_buffer(&self) -> &[u8] {
+        &self.buffer.as_ref()[self.read_count..self.fill_count]
+    }
+
+    /// Returns true if the bytes in the internal buffer that have not been read yet contain a `\n`.
+    /// This fn never calls the underlying `Read` impl, a following `read_line` or `read_until` would not block.
+    #[must_use]
+    pub fn has_complete_line(&self) -> bool {
+        find_byte(b'\n', self.internal_buffer()).is_some()
+    }
+
+    /// Returns the bytes in the internal buffer starting with the first byte that is not valid utf-8.
+    /// An incomplete multibyte sequence at the end of the internal buffer is considered invalid.
+    /// Returns an empty slice if all bytes in the internal buffer are valid utf-8.
+    ///
+    /// After `read_to_string` or `read_line` failed with `ErrorKind::InvalidData` this returns the invalid bytes
+    /// that were retained in the internal buffer.
+    #[must_use]
+    pub fn invalid_remainder(&self) -> &[u8] {
+        let unread = self.internal_buffer();
+        core::str::from_utf8(unread).map_or_else(|e| &unread[e.valid_up_to()..], |_| &[])
+    }
+
+    /// Creates the `ErrorKind::InvalidData` error for invalid utf-8 in the internal buffer.
+    /// Without the `std` feature the `InvalidUtf8Error` payload cannot be attached to the error.
+    #[cfg_attr(
+        not(feature = "std"),
+        allow(clippy::unused_self, clippy::missing_const_for_fn)
+    )]
+    fn utf8_error(&self, valid_appended: usize) -> io::Error {
+        #[cfg(feature = "std")]
+        {
+            let invalid_offset = self.internal_buffer().len() - self.invalid_remainder().len();
+            io::Error::new(
+                ErrorKind::InvalidData,
+                InvalidUtf8Error {
+                    valid_appended,
+                    invalid_offset,
+                },
+            )
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = valid_appended;
+            io::Error::new(ErrorKind::InvalidData, "stream did not contain valid utf-8")
+        }
+    }
+
+    /// Borrows this unowned buffer and associates it with `Read` impl.
+    /// The returned `BorrowedReadBuffer` is both dyn `Read` and dyn `ReadBuf`.
+    /// This may be necessary to call some api function from a library that expects such datatypes.
+    /// The returned `BorrowedReadBuffer` is subject to the lifetime of both the read and self.
+    ///
+    pub const fn borrow<'a, T: Read>(
+        &'a mut self,
+        read: &'a mut T,
+    ) -> generic::BorrowedReadBuffer<'a, T, B> {
+        generic::BorrowedReadBuffer { buffer: self, read }
+    }
+
+    /// Returns an iterator over the utf-8 characters of this buffer and the `Read` impl.
+    /// See `Chars` for details.
+    pub const fn chars<'a, T: Read>(&'a mut self, read: &'a mut T) -> generic::Chars<'a, T, B> {
+        generic::Chars { buffer: self, read }
+    }
+
+    /// Returns a `Read` that yields at most limit bytes from this buffer and the `Read` impl.
+    /// Bytes that are already in the internal buffer count towards the limit first.
+    /// Excess bytes remain in the internal buffer after the returned `LimitedRead` is dropped.
+    pub const fn take<'a, T: Read>(
+        &'a mut self,
+        read: &'a mut T,
+        limit: u64,
+    ) -> generic::LimitedRead<'a, T, B> {
+        generic::LimitedRead {
+            buffer: self,
+            read,
+            limit,
+        }
+    }
+
+    /// Reads some bytes into buf like `read` and also writes them to sink.
+    /// The bytes are only consumed once the sink accepted them.
+    /// Unlike `read` this always reads through the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// Propagated from the sink, no bytes are consumed in this case.
+    /// The sink may have received some of the bytes if its `write_all` failed part way through.
+    ///
+    pub fn read_tee<T: Read, W: Write>(
+        &mut self,
+        read: &mut T,
+        sink: &mut W,
+        buf: &mut [u8],
     ) -> io::Result<usize> {
-        let mut count: usize = 0;
+        self.tee(read, sink).read(buf)
+    }
+
+    /// Borrows this buffer like `borrow` and additionally mirrors every consumed byte into sink.
+    /// See `TeeReadBuffer` for details.
+    ///
+    pub const fn tee<'a, T: Read, W: Write>(
+        &'a mut self,
+        read: &'a mut T,
+        sink: &'a mut W,
+    ) -> generic::TeeReadBuffer<'a, T, W, B> {
+        generic::TeeReadBuffer {
+            buffer: self,
+            read,
+            sink,
+            ignore_sink_errors: false,
+            sink_error: None,
+        }
+    }
+
+    /// Borrows this buffer like `borrow` and additionally passes every consumed byte to the observer.
+    /// See `ObservedReadBuffer` for details.
+    ///
+    pub const fn observe<'a, T: Read, F: FnMut(&[u8])>(
+        &'a mut self,
+        read: &'a mut T,
+        observer: F,
+    ) -> generic::ObservedReadBuffer<'a, T, F, B> {
+        generic::ObservedReadBuffer {
+            buffer: self,
+            read,
+            observer,
+        }
+    }
+
+    /// Returns a Read/BufRead that first yields the bytes of the internal buffer and the first `Read` impl
+    /// and once the first `Read` impl returned EOF continues with the second `Read` impl.
+    pub const fn chain<'a, T: Read, U: Read>(
+        &'a mut self,
+        first: &'a mut T,
+        second: &'a mut U,
+    ) -> generic::ChainedReadBuffer<'a, T, U, B> {
+        generic::ChainedReadBuffer {
+            buffer: self,
+            read: Chain {
+                first,
+                second,
+                first_done: false,
+            },
+        }
+    }
+
+    /// Returns an iterator over the fixed size records of N bytes of this buffer and the `Read` impl.
+    /// See `Records` for details.
+    ///
+    /// # Compile errors
+    /// if N is 0.
+    pub const fn records<'a, const N: usize, T: Read>(
+        &'a mut self,
+        read: &'a mut T,
+    ) -> generic::Records<'a, T, B, N> {
+        const { assert!(N > 0, "records must not be empty") };
+        generic::Records { buffer: self, read }
+    }
+
+    /// Overwrites the entire internal buffer with zeros and discards all unread bytes.
+    /// Use this to scrub secrets, such as passwords, that would otherwise remain in the internal buffer.
+    #[cfg(feature = "zeroize")]
+    pub fn zeroize(&mut self) {
+        zeroize::Zeroize::zeroize(self.buffer.as_mut());
+        self.clear();
+    }
+}
+
+impl Default for ReadBuffer<[u8; 0x4000]> {
+    fn default() -> Self {
+        Self {
+            read_count: 0,
+            fill_count: 0,
+            generation: 0,
+            compaction_threshold: 0,
+            read_calls: 0,
+            buffer: [0; 0x4000],
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>, O: AsRef<[u8]> + AsMut<[u8]>> PartialEq<ReadBuffer<O>>
+    for ReadBuffer<B>
+{
+    /// Only the unread bytes are compared, the size of the buffers and stale bytes are ignored.
+    fn eq(&self, other: &ReadBuffer<O>) -> bool {
+        self.internal_buffer() == other.internal_buffer()
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Eq for ReadBuffer<B> {}
+
+#[cfg(feature = "zeroize")]
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Drop for ReadBuffer<B> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self.buffer.as_mut());
+    }
+}
+
+/// Borrowed dyn Read/ReadBuf of a `UnownedReadBuffer`.
+/// This borrowed version is directly associated with a `Read` impl, but is subject to lifetimes.
+pub type BorrowedReadBuffer<'a, T, const S: usize> = generic::BorrowedReadBuffer<'a, T, [u8; S]>;
+
+/// Same as `BorrowedReadBuffer` but for a `DynReadBuffer`.
+pub type BorrowedDynReadBuffer<'a, T> = generic::BorrowedReadBuffer<'a, T, Box<[u8]>>;
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug
+    for generic::BorrowedReadBuffer<'_, T, B>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.buffer, f)
+    }
+}
+
+impl<'a, T: Read, B: AsRef<[u8]> + AsMut<[u8]>> generic::BorrowedReadBuffer<'a, T, B> {
+    /// Returns true if at least one byte is buffered or can be read from the `Read` impl.
+    /// See `UnownedReadBuffer::has_data_left`.
+    ///
+    /// # Errors
+    /// propagated from Read, including `TimedOut` and `WouldBlock`
+    pub fn has_data_left(&mut self) -> io::Result<bool> {
+        self.buffer.has_data_left(self.read)
+    }
+
+    for_each_int!(borrowed_read_int_fns);
+
+    /// Returns an iterator over the utf-8 characters of this buffer and the `Read` impl.
+    /// See `Chars` for details.
+    pub const fn chars(&mut self) -> generic::Chars<'_, T, B> {
+        generic::Chars {
+            buffer: self.buffer,
+            read: self.read,
+        }
+    }
+
+    /// Returns an iterator over the bytes of this buffer and the `Read` impl.
+    ///
+    /// Unlike `Read::bytes` this takes the bytes directly from the internal buffer
+    /// and only calls the `Read` impl when the internal buffer is empty.
+    /// `Read::bytes` calls `read` with a 1 byte slice for every single byte, which is very slow for unbuffered readers.
+    #[must_use]
+    pub const fn bytes(self) -> generic::Bytes<'a, T, B> {
+        generic::Bytes {
+            buffer: self.buffer,
+            read: self.read,
+        }
+    }
+}
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> Read for generic::BorrowedReadBuffer<'_, T, B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.buffer.read(self.read, buf)
+    }
+
+    #[cfg(feature = "std")]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.buffer.read_vectored(self.read, bufs)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.buffer.read_to_end(self.read, buf)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.buffer.read_to_string(self.read, buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.buffer.read_exact(self.read, buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> BufRead for generic::BorrowedReadBuffer<'_, T, B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.buffer.fill_buf(self.read)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.buffer.consume(amt);
+    }
+
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.buffer.read_until(self.read, byte, buf)
+    }
+
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.buffer.read_line(self.read, buf)
+    }
+}
+
+/// Read buffer that owns its `Read` impl, similar to `std::io::BufReader`.
+/// Use this if an api requires a `BufRead` by value and the `Read` impl does not need to be shared.
+///
+/// `BufRead::fill_buf` follows the contract of `std::io::BufReader`: it only calls the `Read` impl
+/// if all buffered bytes were consumed, so it returns the same bytes until `BufRead::consume` is called.
+pub struct OwnedReadBuffer<R: Read, const S: usize> {
+    /// the buffer
+    buffer: UnownedReadBuffer<S>,
+    /// the read impl
+    read: R,
+}
+
+impl<R: Read, const S: usize> OwnedReadBuffer<R, S> {
+    /// Construct a new Buffer that owns the given `Read` impl.
+    ///
+    /// # Compile errors
+    /// if S is smaller than 16.
+    #[must_use]
+    pub const fn new(read: R) -> Self {
+        Self {
+            buffer: UnownedReadBuffer::new(),
+            read,
+        }
+    }
+
+    /// Returns a reference to the `Read` impl.
+    pub const fn get_ref(&self) -> &R {
+        &self.read
+    }
+
+    /// Returns a mutable reference to the `Read` impl.
+    /// Reading from it directly skips the bytes that are still in the internal buffer.
+    pub const fn get_mut(&mut self) -> &mut R {
+        &mut self.read
+    }
+
+    /// Returns the `Read` impl, all bytes that are still in the internal buffer are lost.
+    pub fn into_inner(self) -> R {
+        self.read
+    }
+}
+
+impl<R: Read + Debug, const S: usize> Debug for OwnedReadBuffer<R, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OwnedReadBuffer")
+            .field("buffer", &self.buffer)
+            .field("read", &self.read)
+            .finish()
+    }
+}
+
+impl<R: Read, const S: usize> Read for OwnedReadBuffer<R, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.buffer.read(&mut self.read, buf)
+    }
+
+    #[cfg(feature = "std")]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.buffer.read_vectored(&mut self.read, bufs)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.buffer.read_to_end(&mut self.read, buf)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.buffer.read_to_string(&mut self.read, buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.buffer.read_exact(&mut self.read, buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, const S: usize> BufRead for OwnedReadBuffer<R, S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.buffer.fill_buf(&mut self.read)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.buffer.consume(amt);
+    }
+
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.buffer.read_until(&mut self.read, byte, buf)
+    }
+
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.buffer.read_line(&mut self.read, buf)
+    }
+}
+
+/// Read/BufRead of an `UnownedReadBuffer` and a `Read` impl that yields at most a limited amount of bytes.
+///
+/// Unlike `Read::take` on the `Read` impl itself this accounts for the bytes already in the internal buffer
+/// and leaves excess bytes in the internal buffer.
+pub type LimitedRead<'a, T, const S: usize> = generic::LimitedRead<'a, T, [u8; S]>;
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> generic::LimitedRead<'_, T, B> {
+    /// Returns how many bytes may still be read.
+    #[must_use]
+    pub const fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the amount of bytes the limit allows to take from a slice of the given length.
+    fn clamp(&self, len: usize) -> usize {
+        usize::try_from(self.limit).map_or(len, |limit| limit.min(len))
+    }
+}
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug for generic::LimitedRead<'_, T, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LimitedRead")
+            .field("buffer", &self.buffer)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> Read for generic::LimitedRead<'_, T, B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.clamp(buf.len());
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let count = self.buffer.read(self.read, &mut buf[..len])?;
+        self.limit -= count as u64;
+        Ok(count)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> BufRead for generic::LimitedRead<'_, T, B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+
+        let buffered = self.buffer.fill_buf(self.read)?.len();
+        let len = self.clamp(buffered);
+        Ok(&self.buffer.internal_buffer()[..len])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = self.clamp(amt);
+        self.buffer.consume(amt);
+        self.limit -= amt as u64;
+    }
+}
+
+/// Iterator over the bytes of an `UnownedReadBuffer` and a `Read` impl. The iterator returns None on EOF.
+/// The `Read` impl is only called when the internal buffer is empty.
+pub type Bytes<'a, T, const S: usize> = generic::Bytes<'a, T, [u8; S]>;
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug for generic::Bytes<'_, T, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.buffer, f)
+    }
+}
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> Iterator for generic::Bytes<'_, T, B> {
+    type Item = io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.buffer.peek_u8(self.read).transpose()?;
+        if byte.is_ok() {
+            self.buffer.read_count += 1;
+        }
+
+        Some(byte)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.buffer.available(), None)
+    }
+}
+
+/// Read/BufRead of an `UnownedReadBuffer` and a `Read` impl that passes every consumed byte to an observer,
+/// for example to compute a checksum.
+///
+/// The observer sees the bytes exactly once and in order when they are handed to the caller or consumed,
+/// not when they are fetched into the internal buffer.
+pub type ObservedReadBuffer<'a, T, F, const S: usize> =
+    generic::ObservedReadBuffer<'a, T, F, [u8; S]>;
+
+impl<T: Read, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]>>
+    generic::ObservedReadBuffer<'_, T, F, B>
+{
+    /// Returns the observer.
+    pub fn into_observer(self) -> F {
+        self.observer
+    }
 
-        if limit == 0 {
-            return Ok(0);
-        }
+    /// Consumes amount bytes from the internal buffer and passes them to the observer.
+    fn consume_observed(&mut self, amount: usize) {
+        let start = self.buffer.read_count;
+        self.buffer.consume(amount);
+        (self.observer)(&self.buffer.buffer.as_ref()[start..start + amount]);
+    }
+}
 
-        if self.available() == 0 && !self.feed(read)? {
+impl<T: Read, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug
+    for generic::ObservedReadBuffer<'_, T, F, B>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.buffer, f)
+    }
+}
+
+impl<T: Read, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]>> Read
+    for generic::ObservedReadBuffer<'_, T, F, B>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
             return Ok(0);
         }
 
-        loop {
-            let mut to_push = &self.buffer[self.read_count..self.fill_count];
-            if count + to_push.len() > limit {
-                to_push = &to_push[..limit - count];
-            }
+        let available = self.buffer.fill_buf(self.read)?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume_observed(count);
+        Ok(count)
+    }
+}
 
-            debug_assert!(count + to_push.len() <= limit);
+#[cfg(feature = "std")]
+impl<T: Read, F: FnMut(&[u8]), B: AsRef<[u8]> + AsMut<[u8]>> BufRead
+    for generic::ObservedReadBuffer<'_, T, F, B>
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.buffer.fill_buf(self.read)
+    }
 
-            for idx in 0..to_push.len() {
-                if to_push[idx] == byte {
-                    to_push = &to_push[..=idx];
-                    buf.extend_from_slice(to_push);
-                    self.read_count += to_push.len();
-                    return Ok(count + to_push.len());
-                }
-            }
+    fn consume(&mut self, amt: usize) {
+        self.consume_observed(amt);
+    }
+}
 
-            buf.extend_from_slice(to_push);
-            count += to_push.len();
-            self.read_count += to_push.len();
-            if count >= limit {
-                return Ok(count);
-            }
+/// Read/BufRead of an `UnownedReadBuffer` and a `Read` impl that writes every consumed byte to a sink.
+///
+/// Bytes are written to the sink when they are handed to the caller or consumed,
+/// not when they are fetched into the internal buffer.
+/// By default an error of the sink is returned to the caller, if possible before any bytes were consumed.
+/// Errors that occur in `BufRead::consume` are returned by the next call instead.
+/// If sink errors are ignored then the first error is kept, the sink is no longer written to
+/// and the error can be retrieved with `take_sink_error`.
+pub type TeeReadBuffer<'a, T, W, const S: usize> = generic::TeeReadBuffer<'a, T, W, [u8; S]>;
 
-            if !self.feed(read)? {
-                return Ok(count);
-            }
-        }
+impl<T: Read, W: Write, B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug
+    for generic::TeeReadBuffer<'_, T, W, B>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TeeReadBuffer")
+            .field("buffer", &self.buffer)
+            .field("ignore_sink_errors", &self.ignore_sink_errors)
+            .field("sink_error", &self.sink_error)
+            .finish()
     }
+}
 
-    /// Reads all remaining bytes into the buffer.
-    /// Those bytes may be from the internal buffer and then from the underlying `Read` impl.
-    /// # Errors
-    /// Propagated from the `Read` impl
-    ///
-    pub fn read_to_end<T: Read>(&mut self, read: &mut T, buf: &mut Vec<u8>) -> io::Result<usize> {
-        if self.available() == 0 && !self.feed(read)? {
-            return Ok(0);
+impl<T: Read, W: Write, B: AsRef<[u8]> + AsMut<[u8]>> generic::TeeReadBuffer<'_, T, W, B> {
+    /// Controls whether errors of the sink are returned by the read fns (the default) or only recorded.
+    #[must_use]
+    pub const fn ignore_sink_errors(mut self, ignore: bool) -> Self {
+        self.ignore_sink_errors = ignore;
+        self
+    }
+
+    /// Takes the recorded error of the sink.
+    /// If sink errors are ignored, this also resumes writing to the sink.
+    pub const fn take_sink_error(&mut self) -> Option<io::Error> {
+        self.sink_error.take()
+    }
+
+    /// Returns the error recorded by `BufRead::consume` if sink errors are not ignored.
+    fn check_sink(&mut self) -> io::Result<()> {
+        if self.ignore_sink_errors {
+            return Ok(());
         }
 
-        let mut count = 0usize;
+        self.sink_error.take().map_or(Ok(()), Err)
+    }
 
-        loop {
-            let push = &self.buffer.as_slice()[self.read_count..self.fill_count];
-            buf.extend_from_slice(push);
-            count += push.len();
-            self.fill_count = 0;
-            self.read_count = 0;
-            if !self.feed(read)? {
-                return Ok(count);
+    /// Writes amount bytes of the internal buffer starting at index start to the sink.
+    fn mirror(&mut self, start: usize, amount: usize) -> io::Result<()> {
+        if self.ignore_sink_errors && self.sink_error.is_some() {
+            return Ok(());
+        }
+
+        let data = &self.buffer.buffer.as_ref()[start..start + amount];
+        match self.sink.write_all(data) {
+            Err(err) if self.ignore_sink_errors => {
+                self.sink_error = Some(err);
+                Ok(())
             }
+            result => result,
         }
     }
+}
 
-    /// Reads all remaining bytes into the String.
-    /// Those bytes may be from the internal buffer and then from the underlying `Read` impl.
-    /// If the `Read` or buffer contained non-valid utf-8 sequences then this fn returns an `io::Error` with Kind `InvalidData`.
-    /// No data is lost in this case as bytes read and already placed in the buf are, in the buf and all remaining bytes
-    /// starting with those that were not valid utf-8 are in the internal buffer and can be fetched with a call to `try_read()`.
-    ///
-    /// # Errors
-    /// Propagated from the `Read` impl
-    /// `ErrorKind::InvalidData` if invalid utf-8 is found.
-    ///
-    pub fn read_to_string<T: Read>(&mut self, read: &mut T, buf: &mut String) -> io::Result<usize> {
-        let mut count = 0usize;
-        if self.available() == 0 && !self.feed(read)? {
+impl<T: Read, W: Write, B: AsRef<[u8]> + AsMut<[u8]>> Read for generic::TeeReadBuffer<'_, T, W, B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check_sink()?;
+        if buf.is_empty() {
             return Ok(0);
         }
 
-        loop {
-            let to_push = &self.buffer[self.read_count..self.fill_count];
-            let mut utf_index = 0;
-            //We leave up to 4 bytes in the buffer for the next cycle because those may be part of an incomplete multibyte sequence.
-            while utf_index + 4 < to_push.len() {
-                utf_index += next_utf8(to_push, utf_index)?;
-            }
+        let available = self.buffer.fill_buf(self.read)?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.mirror(self.buffer.read_count, count)?;
+        self.buffer.consume(count);
+        Ok(count)
+    }
+}
 
-            if utf_index > 0 {
-                buf.push_str(read_utf8(&to_push[..utf_index])?);
-                count += utf_index;
-                self.read_count += utf_index; //feed will compact the buffer.
-            }
+#[cfg(feature = "std")]
+impl<T: Read, W: Write, B: AsRef<[u8]> + AsMut<[u8]>> BufRead
+    for generic::TeeReadBuffer<'_, T, W, B>
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.check_sink()?;
+        self.buffer.fill_buf(self.read)
+    }
 
-            if self.feed(read)? {
-                continue;
-            }
+    fn consume(&mut self, amt: usize) {
+        let start = self.buffer.read_count;
+        self.buffer.consume(amt);
+        if let Err(err) = self.mirror(start, amt) {
+            self.sink_error = Some(err);
+        }
+    }
+}
 
-            //EOF, the rest we must check multibyte for bounds. We must carefully analyze!
-            let to_push = &self.buffer[self.read_count..self.fill_count];
+/// Read/BufRead of an `UnownedReadBuffer` and two `Read` impls that are read one after another.
+/// EOF is only reported once both `Read` impls returned EOF.
+pub type ChainedReadBuffer<'a, T, U, const S: usize> =
+    generic::ChainedReadBuffer<'a, T, U, [u8; S]>;
 
-            //Bounds: we have at least 1 byte remaining at this point.
-            debug_assert!(!to_push.is_empty() && to_push.len() <= 4);
+impl<T: Read, U: Read, B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug
+    for generic::ChainedReadBuffer<'_, T, U, B>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ChainedReadBuffer")
+            .field("buffer", &self.buffer)
+            .field("first_done", &self.read.first_done)
+            .finish()
+    }
+}
 
-            let mut utf_index = 0;
-            loop {
-                if utf_index >= to_push.len() {
-                    break;
-                }
-                let len = utf8_len(to_push[utf_index]);
-                if len > to_push.len() - utf_index {
-                    return Err(io::Error::new(
-                        ErrorKind::InvalidData,
-                        "stream did not contain valid utf-8",
-                    ));
-                }
-                match len {
-                    1 => (),
-                    2 => utf8_cont_assert(to_push[utf_index + 1])?,
-                    3 => {
-                        utf8_cont_assert(to_push[utf_index + 1])?;
-                        utf8_cont_assert(to_push[utf_index + 2])?;
-                    }
-                    4 => {
-                        utf8_cont_assert(to_push[utf_index + 1])?;
-                        utf8_cont_assert(to_push[utf_index + 2])?;
-                        utf8_cont_assert(to_push[utf_index + 3])?;
-                    }
-                    _ => unreachable!(),
-                }
+impl<T: Read, U: Read, B: AsRef<[u8]> + AsMut<[u8]>> Read
+    for generic::ChainedReadBuffer<'_, T, U, B>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.buffer.read(&mut self.read, buf)
+    }
 
-                utf_index += len;
-            }
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.buffer.read_to_end(&mut self.read, buf)
+    }
 
-            buf.push_str(read_utf8(to_push)?);
-            return Ok(count + to_push.len());
-        }
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.buffer.read_to_string(&mut self.read, buf)
     }
 
-    ///
-    /// Reads all bytes into the string until \n is found, or EOF occurred.
-    /// Data is first taken from the internal buffer and then taken from the `Read` impl.
-    ///
-    /// # Major difference from `BufRead`'s `read_line` fn:
-    /// this fn guarantees that no data is discarded when invalid utf-8 is encountered.
-    /// buf may contain some valid bytes in this case but all invalid bytes are retained in the internal buffer
-    /// so that the next call to `read()` can pick them up. call `available()` so you know how many bytes to read!
-    /// `BufRead`'s `read_line` fn just discards the last chunk of invalid data.
-    ///
-    /// # Errors
-    /// Propagated from the `Read` impl
-    /// `ErrorKind::InvalidData` if invalid utf-8 is found.
-    ///
-    pub fn read_line<T: Read>(&mut self, read: &mut T, buf: &mut String) -> io::Result<usize> {
-        let mut count = 0usize;
-        if self.available() == 0 && !self.feed(read)? {
-            return Ok(0);
-        }
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.buffer.read_exact(&mut self.read, buf)
+    }
+}
 
-        loop {
-            for idx in self.read_count..self.fill_count {
-                if self.buffer[idx] == b'\n' {
-                    //We found it!
-                    let to_push = &self.buffer[self.read_count..=idx];
-
-                    let mut utf_index = 0usize;
-                    while utf_index < to_push.len() {
-                        //Panic safety, we do not need to check for bounds here,
-                        //The last byte in the buffer is known to be \n where utf8_len does return 1!
-                        //\n is not a valid continuation so a call to utf8_cont_assert(\n) will always fail.
-                        utf_index += next_utf8(to_push, utf_index)?;
-                    }
-                    buf.push_str(read_utf8(to_push)?);
-                    self.read_count += to_push.len();
-                    return Ok(count + to_push.len());
-                }
-            }
+#[cfg(feature = "std")]
+impl<T: Read, U: Read, B: AsRef<[u8]> + AsMut<[u8]>> BufRead
+    for generic::ChainedReadBuffer<'_, T, U, B>
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.buffer.fill_buf(&mut self.read)
+    }
 
-            let to_push = &self.buffer[self.read_count..self.fill_count];
-            let mut utf_index = 0;
-            //We leave up to 4 bytes in the buffer for the next cycle because those may be part of an incomplete multibyte sequence.
-            while utf_index + 4 < to_push.len() {
-                utf_index += next_utf8(to_push, utf_index)?;
-            }
+    fn consume(&mut self, amt: usize) {
+        self.buffer.consume(amt);
+    }
 
-            if utf_index > 0 {
-                buf.push_str(read_utf8(&to_push[..utf_index])?);
-                count += utf_index;
-                self.read_count += utf_index;
-            }
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.buffer.read_until(&mut self.read, byte, buf)
+    }
 
-            if !self.feed(read)? {
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.buffer.read_line(&mut self.read, buf)
+    }
+}
+
+/// Read impl that reads from first until it returns EOF and then from second.
+struct Chain<'a, T: Read, U: Read> {
+    /// first read ref
+    first: &'a mut T,
+    /// second read ref
+    second: &'a mut U,
+    /// Did first return EOF?
+    first_done: bool,
+}
+
+impl<T: Read, U: Read> Read for Chain<'_, T, U> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.first_done {
+            let count = self.first.read(buf)?;
+            if count > 0 || buf.is_empty() {
                 return Ok(count);
             }
-        }
-    }
 
-    /// `ReadBuf`'s fill buf equivalent. This will only pull data from the underlying read if the internal buffer is empty.
-    /// # Errors
-    /// Propagated from the `Read` impl
-    pub fn fill_buf<T: Read>(&mut self, read: &mut T) -> io::Result<&[u8]> {
-        if self.available() == 0 && !self.feed(read)? {
-            return Ok(&[]);
+            self.first_done = true;
         }
 
-        Ok(&self.buffer.as_slice()[self.read_count..self.fill_count])
+        self.second.read(buf)
     }
+}
 
-    /// `ReadBuf`'s consume fn.
-    /// In general, it should be paired with calls to `fill_buf`
+/// Iterator over the utf-8 characters of an `UnownedReadBuffer` and a `Read` impl.
+/// Each call to next behaves like `UnownedReadBuffer::read_char`. The iterator returns None on EOF.
+///
+/// If invalid utf-8 is encountered then an `ErrorKind::InvalidData` error is returned and
+/// the invalid bytes remain in the internal buffer. Calling next again would return the same error,
+/// call `consume` to skip the invalid bytes and then continue iterating.
+pub type Chars<'a, T, const S: usize> = generic::Chars<'a, T, [u8; S]>;
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> generic::Chars<'_, T, B> {
+    /// Skips bytes in the internal buffer, for example bytes that are not valid utf-8.
     /// # Panics
     /// This function will panic if amt is > available
     ///
     pub fn consume(&mut self, amt: usize) {
-        assert!(self.read_count + amt <= self.fill_count);
-        self.read_count += amt;
+        self.buffer.consume(amt);
     }
+}
 
-    /// Borrows this unowned buffer and associates it with `Read` impl.
-    /// The returned `BorrowedReadBuffer` is both dyn `Read` and dyn `ReadBuf`.
-    /// This may be necessary to call some api function from a library that expects such datatypes.
-    /// The returned `BorrowedReadBuffer` is subject to the lifetime of both the read and self.
-    ///
-    pub fn borrow<'a, T: Read>(&'a mut self, read: &'a mut T) -> BorrowedReadBuffer<'a, T, S> {
-        BorrowedReadBuffer { buffer: self, read }
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug for generic::Chars<'_, T, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.buffer, f)
     }
 }
 
-/// Borrowed dyn Read/ReadBuf of a `UnownedReadBuffer`.
-/// This borrowed version is directly associated with a `Read` impl, but is subject to lifetimes.
-pub struct BorrowedReadBuffer<'a, T: Read, const S: usize> {
-    /// buffer ref
-    buffer: &'a mut UnownedReadBuffer<S>,
-    /// read ref
-    read: &'a mut T,
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> Iterator for generic::Chars<'_, T, B> {
+    type Item = io::Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.read_char(self.read).transpose()
+    }
 }
 
-impl<T: Read, const S: usize> Debug for BorrowedReadBuffer<'_, T, S> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+/// Iterator over the fixed size records of N bytes of an `UnownedReadBuffer` and a `Read` impl.
+/// Each call to next behaves like `UnownedReadBuffer::read_array`. The iterator returns None on EOF.
+///
+/// If EOF occurs in the middle of a record then an `ErrorKind::UnexpectedEof` error is returned,
+/// the bytes of the truncated record are lost and the next call returns None.
+pub type Records<'a, T, const S: usize, const N: usize> = generic::Records<'a, T, [u8; S], N>;
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]> + Debug, const N: usize> Debug
+    for generic::Records<'_, T, B, N>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         Debug::fmt(&self.buffer, f)
     }
 }
 
-impl<T: Read, const S: usize> Read for BorrowedReadBuffer<'_, T, S> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.buffer.read(self.read, buf)
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>, const N: usize> Iterator
+    for generic::Records<'_, T, B, N>
+{
+    type Item = io::Result<[u8; N]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = [0u8; N];
+        match self.buffer.read_exact_counted(self.read, &mut record) {
+            Ok(()) => Some(Ok(record)),
+            Err((0, err)) if err.kind() == ErrorKind::UnexpectedEof => None,
+            Err((_, err)) if err.kind() == ErrorKind::UnexpectedEof => Some(Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "EOF in the middle of a record",
+            ))),
+            Err((_, err)) => Some(Err(err)),
+        }
     }
+}
 
-    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
-        self.buffer.read_to_end(self.read, buf)
+/// A read position of an `UnownedReadBuffer` returned by `UnownedReadBuffer::mark`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    /// The read count at the time of the mark
+    read_count: usize,
+    /// The generation of the buffer at the time of the mark
+    generation: u64,
+}
+
+/// Progress of an exact read that is performed with `UnownedReadBuffer::resume_read_exact`.
+/// Tracks how many bytes of the destination are already filled, so the read can be resumed after `WouldBlock`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadExactState {
+    /// bytes of the destination that are filled
+    filled: usize,
+    /// total bytes to read
+    len: usize,
+}
+
+impl ReadExactState {
+    /// Starts an exact read of len bytes.
+    #[must_use]
+    pub const fn new(len: usize) -> Self {
+        Self { filled: 0, len }
     }
 
-    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
-        self.buffer.read_to_string(self.read, buf)
+    /// Returns the amount of bytes at the start of the destination that are filled and valid.
+    #[must_use]
+    pub const fn filled(&self) -> usize {
+        self.filled
     }
 
-    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
-        self.buffer.read_exact(self.read, buf)
+    /// Returns the total amount of bytes to read.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the total amount of bytes to read is 0.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if all bytes were read.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.filled == self.len
     }
 }
 
-impl<T: Read, const S: usize> BufRead for BorrowedReadBuffer<'_, T, S> {
-    fn fill_buf(&mut self) -> io::Result<&[u8]> {
-        self.buffer.fill_buf(self.read)
+/// Error returned by `UnownedReadBuffer::reset` if the bytes read since the mark are no longer in the internal buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkInvalidated;
+
+impl Display for MarkInvalidated {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("the mark was invalidated because the internal buffer was compacted or cleared")
     }
+}
 
-    fn consume(&mut self, amt: usize) {
-        self.buffer.consume(amt);
+impl core::error::Error for MarkInvalidated {}
+
+/// Outcome of `UnownedReadBuffer::read_nul_terminated`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NulReadOutcome {
+    /// The NUL byte was found and consumed. Contains the amount of bytes appended to buf.
+    Terminated(usize),
+    /// limit bytes were appended to buf without finding the NUL byte.
+    LimitReached,
+    /// EOF occurred before the NUL byte was found. Contains the amount of bytes appended to buf.
+    Eof(usize),
+}
+
+/// Result of `UnownedReadBuffer::poll_readable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadState {
+    /// Bytes can be read. Contains the amount of bytes in the internal buffer.
+    Ready(usize),
+    /// The internal buffer is empty and the `Read` impl returned Ok(0).
+    Eof,
+    /// The internal buffer is empty and the `Read` impl returned `ErrorKind::WouldBlock`.
+    NotReady,
+}
+
+/// Outcome of `UnownedReadBuffer::read_exact_budgeted` and `UnownedWriteBuffer::write_all_budgeted`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budgeted {
+    /// The operation completed within the budget.
+    Complete,
+    /// The budget was used up or the underlying impl returned `ErrorKind::WouldBlock` before the operation completed.
+    /// Contains the amount of bytes that were processed, resume the operation with the remaining bytes.
+    Exhausted(usize),
+}
+
+/// Payload of the `ErrorKind::InvalidData` errors returned by `read_to_string` and `read_line`
+/// when invalid utf-8 is encountered. Use `io::Error::get_ref` and `downcast_ref` to obtain it.
+///
+/// The payload is only attached with the `std` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidUtf8Error {
+    /// How many valid bytes were appended to the String before the invalid utf-8 was encountered
+    valid_appended: usize,
+    /// Offset of the first invalid byte in the internal buffer
+    invalid_offset: usize,
+}
+
+impl InvalidUtf8Error {
+    /// Returns how many valid bytes were appended to the String before the invalid utf-8 was encountered.
+    #[must_use]
+    pub const fn valid_appended(&self) -> usize {
+        self.valid_appended
     }
 
-    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
-        self.buffer.read_until(self.read, byte, buf)
+    /// Returns the offset of the first invalid byte within `internal_buffer()`.
+    /// All bytes before this offset are valid utf-8 but were not appended to the String.
+    #[must_use]
+    pub const fn invalid_offset(&self) -> usize {
+        self.invalid_offset
     }
+}
 
-    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
-        self.buffer.read_line(self.read, buf)
+impl Display for InvalidUtf8Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "stream did not contain valid utf-8, {} valid bytes were appended",
+            self.valid_appended
+        )
     }
 }
 
-/// This fn returns the size of the next utf-8 character in bytes.
-/// this can return 1,2,3,4 or Err.
-/// Err is returned if the bit for an utf-8 continuation byte is set on the first byte.
-/// Err is returned if any of the subsequent bytes do NOT have the utf-8 continuation bit set.
-///
-/// This fn does not check the buffer for bounds and assumes the caller ensures that at least 4 bytes
-/// or an invalid utf-8 sequence is encountered before end of buffer.
-fn next_utf8(to_push: &[u8], count: usize) -> io::Result<usize> {
-    Ok(match utf8_len(to_push[count]) {
-        1 => 1,
-        2 => {
-            utf8_cont_assert(to_push[count + 1])?;
-            2
-        }
-        3 => {
-            utf8_cont_assert(to_push[count + 1])?;
-            utf8_cont_assert(to_push[count + 2])?;
-            3
-        }
-        4 => {
-            utf8_cont_assert(to_push[count + 1])?;
-            utf8_cont_assert(to_push[count + 2])?;
-            utf8_cont_assert(to_push[count + 3])?;
-            4
-        }
-        _ => {
-            return Err(io::Error::new(
-                ErrorKind::InvalidData,
-                "stream did not contain valid utf-8",
-            ))
+impl core::error::Error for InvalidUtf8Error {}
+
+/// This fn returns the index of the first occurrence of byte in haystack.
+#[cfg(feature = "memchr")]
+fn find_byte(byte: u8, haystack: &[u8]) -> Option<usize> {
+    memchr::memchr(byte, haystack)
+}
+
+/// This fn returns the index of the first occurrence of byte in haystack.
+#[cfg(not(feature = "memchr"))]
+fn find_byte(byte: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|b| *b == byte)
+}
+
+/// This fn returns the longest prefix of the bytes that is valid utf-8 and does not end in
+/// an incomplete multibyte sequence. Only the last up to 4 bytes are inspected to find the end of the prefix,
+/// the prefix itself is validated with a single call to `from_utf8`.
+/// An incomplete multibyte sequence at the end of the bytes is not an error, it is just not part of the prefix.
+fn valid_utf8_prefix(bytes: &[u8]) -> io::Result<&str> {
+    let mut boundary = bytes.len();
+    //The first byte of the last character is at most 3 bytes before the last byte.
+    for idx in (bytes.len().saturating_sub(4)..bytes.len()).rev() {
+        if bytes[idx] & 0b1100_0000 != 0b1000_0000 {
+            if utf8_len(bytes[idx]) > bytes.len() - idx {
+                boundary = idx;
+            }
+            break;
         }
-    })
+    }
+
+    read_utf8(&bytes[..boundary])
 }
 
 /// This fn does a `utf::from_utf8` safety check,
 /// and then converts errors that should never exist (`Utf8Error`) to `io::Error`
 fn read_utf8(to_push: &[u8]) -> io::Result<&str> {
-    core::str::from_utf8(to_push).map_or_else(
+    #[cfg(feature = "simd")]
+    let result = simdutf8::basic::from_utf8(to_push);
+    #[cfg(not(feature = "simd"))]
+    let result = core::str::from_utf8(to_push);
+
+    result.map_or_else(
         |_| {
             Err(io::Error::new(
                 ErrorKind::InvalidData,
@@ -879,7 +4684,61 @@ fn read_utf8(to_push: &[u8]) -> io::Result<&str> {
     )
 }
 
+/// This fn maps signed integers to unsigned integers so that values with a small magnitude have a short varint.
+#[allow(clippy::cast_sign_loss)] //The bits are reinterpreted on purpose.
+const fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// This fn reverses `zigzag_encode`.
+#[allow(clippy::cast_possible_wrap)] //The bits are reinterpreted on purpose.
+const fn zigzag_decode(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// This fn reverses the zigzag encoding for values that fit into an i32.
+#[allow(clippy::cast_possible_wrap)] //The bits are reinterpreted on purpose.
+const fn zigzag_decode_32(value: u32) -> i32 {
+    (value >> 1) as i32 ^ -((value & 1) as i32)
+}
+
+/// This fn decodes all bytes in src and appends the result to buf.
+/// If last is true then the decoder also flushes any incomplete sequence it still retains.
+#[cfg(feature = "encoding")]
+fn decode_to_string(decoder: &mut encoding_rs::Decoder, src: &[u8], buf: &mut String, last: bool) {
+    let mut src = src;
+    loop {
+        buf.reserve(
+            decoder
+                .max_utf8_buffer_length(src.len())
+                .unwrap_or(src.len())
+                .max(4),
+        );
+        let (result, read, _) = decoder.decode_to_string(src, buf, last);
+        src = &src[read..];
+        if result == encoding_rs::CoderResult::InputEmpty {
+            return;
+        }
+    }
+}
+
+/// This fn returns the error of the limited read fns for a limit that cannot hold the next character.
+/// Returning Ok(0) instead would look like EOF while bytes are still buffered.
+#[cfg_attr(not(feature = "std"), allow(clippy::missing_const_for_fn))]
+fn limit_too_small_error() -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        "limit is too small for the next character",
+    )
+}
+
+/// This fn returns true for the ASCII whitespace that separates tokens in `read_token`.
+const fn is_token_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n')
+}
+
 /// This fn returns err if the given byte does not have the utf-8 continuation bits set.
+#[cfg_attr(not(feature = "std"), allow(clippy::missing_const_for_fn))]
 fn utf8_cont_assert(cont: u8) -> io::Result<()> {
     if cont & 0b1100_0000 == 0b1000_0000 {
         return Ok(());
@@ -0,0 +1,622 @@
+//! Read buffer that stores its bytes in a ring and never moves them.
+
+use crate::generic;
+use crate::generic::RingReadBuffer;
+use crate::io;
+use crate::io::{ErrorKind, Read};
+use crate::{for_each_int, read_int_fns, ByteOrder, EndianInt};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
+/// Ring buffer variant of `UnownedReadBuffer` that never has to be compacted.
+///
+/// `UnownedReadBuffer` moves the unread bytes to the start of the internal buffer before every read from the `Read` impl.
+/// This type reads into the free space behind the unread bytes instead, wrapping around at the end,
+/// so bytes are never moved. This pays off when many bytes usually remain buffered between reads.
+/// The unread bytes are available as up to two slices, see `internal_buffer_slices`.
+/// `fill_buf` only returns the first of those slices.
+pub type UnownedRingReadBuffer<const S: usize> = RingReadBuffer<[u8; S]>;
+
+impl<const S: usize> RingReadBuffer<[u8; S]> {
+    /// Construct a new Buffer
+    ///
+    /// # Compile errors
+    /// if S is smaller than 16.
+    #[must_use]
+    pub const fn new() -> Self {
+        const { assert!(S >= 16, "UnownedRingReadBuffer is too small") };
+
+        Self {
+            head: 0,
+            len: 0,
+            buffer: [0; S],
+        }
+    }
+}
+
+impl<const S: usize> Default for RingReadBuffer<[u8; S]> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> RingReadBuffer<B> {
+    /// Returns the size of the internal buffer.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.buffer.as_ref().len()
+    }
+
+    /// returns the amount of bytes that can still be read from the internal buffer.
+    #[must_use]
+    pub const fn available(&self) -> usize {
+        self.len
+    }
+
+    /// returns true if there are no bytes left to read in the internal buffer.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the amount of bytes that can be added to the internal buffer.
+    #[must_use]
+    pub fn available_space(&self) -> usize {
+        self.size() - self.len
+    }
+
+    /// Returns the unread bytes of the internal buffer.
+    /// The second slice is only non-empty if the unread bytes wrap around the end of the internal buffer.
+    /// This fn never calls the underlying `Read` impl.
+    #[must_use]
+    pub fn internal_buffer_slices(&self) -> (&[u8], &[u8]) {
+        let buffer = self.buffer.as_ref();
+        let end = self.head + self.len;
+        if end <= buffer.len() {
+            return (&buffer[self.head..end], &[]);
+        }
+
+        (&buffer[self.head..], &buffer[..end - buffer.len()])
+    }
+
+    /// Discards all unread bytes of the internal buffer and returns how many were discarded.
+    pub const fn clear(&mut self) -> usize {
+        let discarded = self.len;
+        self.head = 0;
+        self.len = 0;
+        discarded
+    }
+
+    /// reads some bytes from the read impl into the free space behind the unread bytes.
+    fn feed<T: Read>(&mut self, read: &mut T) -> io::Result<bool> {
+        if self.len == 0 {
+            //Start at the front again so the next reads get the largest contiguous space.
+            self.head = 0;
+        }
+
+        let size = self.size();
+        let tail = (self.head + self.len) % size;
+        let end = if tail < self.head || self.len == size {
+            self.head
+        } else {
+            size
+        };
+
+        let count = loop {
+            match read.read(&mut self.buffer.as_mut()[tail..end]) {
+                //Like std, Interrupted is retried.
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                result => break result?,
+            }
+        };
+        if count == 0 {
+            return Ok(false);
+        }
+
+        self.len += count;
+        Ok(true)
+    }
+
+    /// Reads bytes only from the internal buffer and returns how many were copied into buffer.
+    /// This fn never calls the underlying `Read` impl.
+    pub fn try_read(&mut self, buffer: &mut [u8]) -> usize {
+        let (first, second) = self.internal_buffer_slices();
+        let from_first = first.len().min(buffer.len());
+        buffer[..from_first].copy_from_slice(&first[..from_first]);
+        let from_second = second.len().min(buffer.len() - from_first);
+        buffer[from_first..from_first + from_second].copy_from_slice(&second[..from_second]);
+        let count = from_first + from_second;
+        self.consume(count);
+        count
+    }
+
+    /// Reads some bytes into buffer, the `Read` impl is called at most once and only if the internal buffer is empty.
+    /// If the internal buffer is empty and buffer is at least as large as the internal buffer
+    /// then the `Read` impl reads directly into buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read<T: Read>(&mut self, read: &mut T, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        if self.len == 0 {
+            if buffer.len() >= self.size() {
+                //LARGE READ, going through the internal buffer would only add a copy.
+                return read.read(buffer);
+            }
+
+            if !self.feed(read)? {
+                return Ok(0);
+            }
+        }
+
+        Ok(self.try_read(buffer))
+    }
+
+    /// This fn will read the entire buffer from either the internal buffer or the
+    /// `Read` impl. Multiple calls to the read impl may be made if necessary to fill the buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the buffer was filled.
+    ///
+    pub fn read_exact<T: Read>(&mut self, read: &mut T, buffer: &mut [u8]) -> io::Result<()> {
+        let mut count = self.try_read(buffer);
+        while count < buffer.len() {
+            if buffer.len() - count >= self.size() {
+                //LARGE READ, the remainder is read directly into the buffer.
+                match read.read(&mut buffer[count..]) {
+                    Ok(0) => return Err(io::Error::from(ErrorKind::UnexpectedEof)),
+                    Ok(copied) => count += copied,
+                    //Like std, Interrupted is retried.
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+                continue;
+            }
+
+            if !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+
+            count += self.try_read(&mut buffer[count..]);
+        }
+
+        Ok(())
+    }
+
+    /// Reads all remaining bytes into the buffer.
+    /// Those bytes may be from the internal buffer and then from the underlying `Read` impl.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_to_end<T: Read>(&mut self, read: &mut T, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let (first, second) = self.internal_buffer_slices();
+        buf.extend_from_slice(first);
+        buf.extend_from_slice(second);
+        let buffered = self.clear();
+        Ok(buffered + read.read_to_end(buf)?)
+    }
+
+    /// Reads until either EOF happens or the desired byte is found.
+    /// The desired byte is appended to buf. Returns the amount of appended bytes.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn read_until<T: Read>(
+        &mut self,
+        read: &mut T,
+        byte: u8,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize> {
+        let mut count = 0usize;
+        loop {
+            let available = self.fill_buf(read)?;
+            if available.is_empty() {
+                return Ok(count);
+            }
+
+            if let Some(index) = crate::find_byte(byte, available) {
+                buf.extend_from_slice(&available[..=index]);
+                self.consume(index + 1);
+                return Ok(count + index + 1);
+            }
+
+            let len = available.len();
+            buf.extend_from_slice(available);
+            self.consume(len);
+            count += len;
+        }
+    }
+
+    /// Returns the first contiguous slice of unread bytes.
+    /// The `Read` impl is called once if the internal buffer is empty.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    ///
+    pub fn fill_buf<T: Read>(&mut self, read: &mut T) -> io::Result<&[u8]> {
+        if self.len == 0 {
+            self.feed(read)?;
+        }
+
+        Ok(self.internal_buffer_slices().0)
+    }
+
+    /// Calls the `Read` impl until at least min bytes are in the internal buffer and returns the amount of buffered bytes.
+    /// Fewer than min bytes are only buffered if EOF occurred. The unread bytes are never moved,
+    /// use `internal_buffer_slices` to access them.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidInput` if min is larger than the internal buffer.
+    ///
+    pub fn fill_at_least<T: Read>(&mut self, read: &mut T, min: usize) -> io::Result<usize> {
+        if min > self.size() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "min is larger than the internal buffer",
+            ));
+        }
+
+        while self.len < min {
+            if !self.feed(read)? {
+                break;
+            }
+        }
+
+        Ok(self.len)
+    }
+
+    /// `ReadBuf`'s consume fn.
+    /// In general, it should be paired with calls to `fill_buf`
+    /// # Panics
+    /// This function will panic if amt is > available
+    ///
+    pub fn consume(&mut self, amt: usize) {
+        assert!(amt <= self.len);
+        self.len -= amt;
+        if self.len == 0 {
+            self.head = 0;
+            return;
+        }
+
+        //amt is at most len, so the head wraps around at most once.
+        self.head += amt;
+        if self.head >= self.size() {
+            self.head -= self.size();
+        }
+    }
+
+    /// Returns the unread byte at the given offset, the offset wraps around the end of the internal buffer.
+    fn byte_at(&self, offset: usize) -> u8 {
+        let buffer = self.buffer.as_ref();
+        buffer[(self.head + offset) % buffer.len()]
+    }
+
+    /// Reads all remaining bytes into the String.
+    /// If invalid utf-8 is found then buf may contain some valid bytes,
+    /// but all invalid bytes are retained in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. Unlike `UnownedReadBuffer` the error carries no `InvalidUtf8Error`.
+    ///
+    pub fn read_to_string<T: Read>(&mut self, read: &mut T, buf: &mut String) -> io::Result<usize> {
+        self.append_utf8(read, false, buf)
+    }
+
+    /// Reads until EOF or until a \n was appended to the String. Returns the amount of appended bytes.
+    /// If invalid utf-8 is found then buf may contain some valid bytes,
+    /// but all invalid bytes are retained in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::InvalidData` if invalid utf-8 is found. Unlike `UnownedReadBuffer` the error carries no `InvalidUtf8Error`.
+    ///
+    pub fn read_line<T: Read>(&mut self, read: &mut T, buf: &mut String) -> io::Result<usize> {
+        self.append_utf8(read, true, buf)
+    }
+
+    /// Appends valid utf-8 to buf until EOF or, if newline is true, until a \n was appended.
+    fn append_utf8<T: Read>(
+        &mut self,
+        read: &mut T,
+        newline: bool,
+        buf: &mut String,
+    ) -> io::Result<usize> {
+        let mut count = 0usize;
+        loop {
+            if self.len == 0 && !self.feed(read)? {
+                return Ok(count);
+            }
+
+            let first = self.internal_buffer_slices().0;
+            let newline_idx = if newline {
+                crate::find_byte(b'\n', first)
+            } else {
+                None
+            };
+            let end = newline_idx.map_or(first.len(), |idx| idx + 1);
+            let valid = crate::valid_utf8_prefix(&first[..end])?;
+            let valid_len = valid.len();
+            buf.push_str(valid);
+            self.consume(valid_len);
+            count += valid_len;
+            if newline_idx.is_some() {
+                return Ok(count);
+            }
+
+            if valid_len < end {
+                //The character at the end of the first slice continues at the start of the internal buffer.
+                count += self.append_char(read, buf)?;
+            }
+        }
+    }
+
+    /// Appends the multibyte character that starts at the first unread byte to buf.
+    fn append_char<T: Read>(&mut self, read: &mut T, buf: &mut String) -> io::Result<usize> {
+        let width = crate::utf8_len(self.byte_at(0));
+        if width == 0 || self.fill_at_least(read, width)? < width {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "stream did not contain valid utf-8",
+            ));
+        }
+
+        let mut bytes = [0u8; 4];
+        for (offset, byte) in bytes[..width].iter_mut().enumerate() {
+            *byte = self.byte_at(offset);
+        }
+
+        buf.push_str(crate::read_utf8(&bytes[..width])?);
+        self.consume(width);
+        Ok(width)
+    }
+
+    /// Reads exactly N bytes and returns them as an array.
+    /// This fn behaves like `read_exact`, N may be larger than the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before N bytes were read.
+    ///
+    pub fn read_array<const N: usize, T: Read>(&mut self, read: &mut T) -> io::Result<[u8; N]> {
+        let mut array = [0u8; N];
+        self.read_exact(read, &mut array)?;
+        Ok(array)
+    }
+
+    /// Reads the N bytes of a fixed size integer, they are only consumed if all of them could be read.
+    fn read_int_bytes<const N: usize, T: Read>(&mut self, read: &mut T) -> io::Result<[u8; N]> {
+        let mut array = [0u8; N];
+        self.read_int_into(read, &mut array)?;
+        Ok(array)
+    }
+
+    /// Fills bytes with the next unread bytes, they are only consumed if all of them could be read.
+    fn read_int_into<T: Read>(&mut self, read: &mut T, bytes: &mut [u8]) -> io::Result<()> {
+        //The integers are at most 16 bytes, so they never exceed the internal buffer.
+        if self.fill_at_least(read, bytes.len())? < bytes.len() {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+
+        let first = self.internal_buffer_slices().0;
+        if first.len() >= bytes.len() {
+            //Most integers do not cross the end of the internal buffer, they are copied in one go.
+            bytes.copy_from_slice(&first[..bytes.len()]);
+            self.consume(bytes.len());
+            return Ok(());
+        }
+
+        self.try_read(bytes);
+        Ok(())
+    }
+
+    for_each_int!(read_int_fns);
+
+    /// Reads an integer in the given byte order, for example `read_int::<u32, BigEndian>(read)`.
+    /// The bytes are only consumed if all of them could be read, on error they remain in the internal buffer.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_int<I: EndianInt, O: ByteOrder>(&mut self, read: &mut impl Read) -> io::Result<I> {
+        let mut bytes = I::Bytes::default();
+        self.read_int_into(read, bytes.as_mut())?;
+        Ok(O::decode(bytes))
+    }
+
+    /// Reads big endian IEEE 754 bits as f32, NaN payloads are preserved. See `read_u32_be`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_f32_be<T: Read>(&mut self, read: &mut T) -> io::Result<f32> {
+        Ok(f32::from_bits(self.read_u32_be(read)?))
+    }
+
+    /// Reads little endian IEEE 754 bits as f32, NaN payloads are preserved. See `read_u32_le`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_f32_le<T: Read>(&mut self, read: &mut T) -> io::Result<f32> {
+        Ok(f32::from_bits(self.read_u32_le(read)?))
+    }
+
+    /// Reads big endian IEEE 754 bits as f64, NaN payloads are preserved. See `read_u64_be`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_f64_be<T: Read>(&mut self, read: &mut T) -> io::Result<f64> {
+        Ok(f64::from_bits(self.read_u64_be(read)?))
+    }
+
+    /// Reads little endian IEEE 754 bits as f64, NaN payloads are preserved. See `read_u64_le`.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before enough bytes were buffered.
+    ///
+    pub fn read_f64_le<T: Read>(&mut self, read: &mut T) -> io::Result<f64> {
+        Ok(f64::from_bits(self.read_u64_le(read)?))
+    }
+
+    /// Reads an unsigned LEB128 varint.
+    /// The bytes of the varint are only consumed if the varint was read successfully.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 10 bytes or does not fit into an u64.
+    ///
+    pub fn read_varint_u64<T: Read>(&mut self, read: &mut T) -> io::Result<u64> {
+        let mut value = 0u64;
+        for idx in 0..10 {
+            if idx >= self.len && !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+
+            let byte = self.byte_at(idx);
+            //Only a single bit is left for the 10th byte.
+            if idx == 9 && byte > 1 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "varint does not fit into u64",
+                ));
+            }
+
+            value |= u64::from(byte & 0x7F) << (idx * 7);
+            if byte & 0x80 == 0 {
+                self.consume(idx + 1);
+                return Ok(value);
+            }
+        }
+
+        //The 10th byte either terminates the varint or is rejected above.
+        unreachable!()
+    }
+
+    /// Reads an unsigned LEB128 varint of at most 5 bytes.
+    /// The bytes of the varint are only consumed if the varint was read successfully.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 5 bytes or does not fit into an u32.
+    ///
+    pub fn read_varint_u32<T: Read>(&mut self, read: &mut T) -> io::Result<u32> {
+        let mut value = 0u32;
+        for idx in 0..5 {
+            if idx >= self.len && !self.feed(read)? {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof));
+            }
+
+            let byte = self.byte_at(idx);
+            //Only 4 bits are left for the 5th byte.
+            if idx == 4 && byte > 0x0F {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "varint does not fit into u32",
+                ));
+            }
+
+            value |= u32::from(byte & 0x7F) << (idx * 7);
+            if byte & 0x80 == 0 {
+                self.consume(idx + 1);
+                return Ok(value);
+            }
+        }
+
+        //The 5th byte either terminates the varint or is rejected above.
+        unreachable!()
+    }
+
+    /// Reads a zigzag encoded signed LEB128 varint, this is the encoding protobuf uses for sint64.
+    /// The bytes of the varint are only consumed if the varint was read successfully.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 10 bytes or does not fit into an i64.
+    ///
+    pub fn read_varint_i64<T: Read>(&mut self, read: &mut T) -> io::Result<i64> {
+        Ok(crate::zigzag_decode(self.read_varint_u64(read)?))
+    }
+
+    /// Reads a zigzag encoded signed LEB128 varint, this is the encoding protobuf uses for sint32.
+    /// The bytes of the varint are only consumed if the varint was read successfully.
+    ///
+    /// # Errors
+    /// Propagated from the `Read` impl
+    /// `ErrorKind::UnexpectedEof` if the `Read` impl returns Ok(0) before the varint is complete.
+    /// `ErrorKind::InvalidData` if the varint is longer than 5 bytes or does not fit into an i32.
+    ///
+    pub fn read_varint_i32<T: Read>(&mut self, read: &mut T) -> io::Result<i32> {
+        Ok(crate::zigzag_decode_32(self.read_varint_u32(read)?))
+    }
+
+    /// Borrows this buffer and associates it with `Read` impl.
+    /// The returned `BorrowedRingReadBuffer` is both dyn `Read` and dyn `ReadBuf`.
+    pub const fn borrow<'a, T: Read>(
+        &'a mut self,
+        read: &'a mut T,
+    ) -> generic::BorrowedRingReadBuffer<'a, T, B> {
+        generic::BorrowedRingReadBuffer { buffer: self, read }
+    }
+}
+
+/// Read/BufRead of an `UnownedRingReadBuffer` and a `Read` impl.
+pub type BorrowedRingReadBuffer<'a, T, const S: usize> =
+    generic::BorrowedRingReadBuffer<'a, T, [u8; S]>;
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]> + Debug> Debug
+    for generic::BorrowedRingReadBuffer<'_, T, B>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.buffer, f)
+    }
+}
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> Read for generic::BorrowedRingReadBuffer<'_, T, B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.buffer.read(self.read, buf)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.buffer.read_to_end(self.read, buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.buffer.read_exact(self.read, buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>> BufRead for generic::BorrowedRingReadBuffer<'_, T, B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.buffer.fill_buf(self.read)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.buffer.consume(amt);
+    }
+
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.buffer.read_until(self.read, byte, buf)
+    }
+}
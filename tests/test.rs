@@ -1,8 +1,14 @@
 extern crate core;
 
 use rand::random;
-use std::io::{Cursor, Write};
-use unowned_buf::{UnownedReadBuffer, UnownedWriteBuffer};
+use std::io::{Cursor, ErrorKind, Read, Write};
+use unowned_buf::{
+    BigEndian, BorrowedDynReadBuffer, BorrowedDynWriteBuffer, BorrowedReadBuffer,
+    BorrowedWriteBuffer, Budgeted, ByteOrder, Chars, DynReadBuffer, DynWriteBuffer, EndianInt,
+    InvalidUtf8Error, LittleEndian, MarkInvalidated, NativeEndian, NulReadOutcome, OwnedReadBuffer,
+    OwnedWriteBuffer, ReadExactState, ReadState, UnownedReadBuffer, UnownedReadExt,
+    UnownedRingReadBuffer, UnownedWriteBuffer,
+};
 
 #[cfg(not(miri))]
 const COUNT: usize = 0x1_00_00;
@@ -16,6 +22,7 @@ const RAND_SIZE: usize = 63;
 const RAND_SIZE: usize = 4095;
 
 #[test]
+#[allow(clippy::drop_non_drop)]
 pub fn test_read() {
     let mut data = vec![0u8; COUNT];
     for j in data.iter_mut() {
@@ -47,6 +54,7 @@ pub fn test_read() {
 }
 
 #[test]
+#[allow(clippy::drop_non_drop)]
 pub fn test_read_exact() {
     let mut data = vec![0u8; COUNT];
     for j in data.iter_mut() {
@@ -84,6 +92,7 @@ pub fn test_read_exact() {
 }
 
 #[test]
+#[allow(clippy::drop_non_drop)]
 pub fn test_read_until() {
     let mut data = vec![0u8; COUNT];
     for j in data.iter_mut() {
@@ -135,6 +144,7 @@ pub fn test_read_until_limit_bug() {
 }
 
 #[test]
+#[allow(clippy::drop_non_drop)]
 pub fn test_read_until_limit_large() {
     let mut data = vec![0u8; COUNT];
     for j in data.iter_mut() {
@@ -168,6 +178,7 @@ pub fn test_read_until_limit_large() {
     assert_eq!(copy, data);
 }
 
+#[allow(clippy::almost_complete_range)]
 fn ascii() -> Vec<u8> {
     let mut dta: Vec<u8> = Vec::new();
     for i in b'A'..b'Z' {
@@ -192,6 +203,7 @@ fn ascii() -> Vec<u8> {
 }
 
 #[test]
+#[allow(clippy::drop_non_drop)]
 pub fn test_read_to_end() {
     let mut data = vec![0u8; COUNT];
     for j in data.iter_mut() {
@@ -220,6 +232,7 @@ pub fn test_read_to_end() {
 }
 
 #[test]
+#[allow(clippy::drop_non_drop)]
 pub fn test_read_string() {
     let characters = ascii();
 
@@ -259,6 +272,7 @@ pub fn test_read_string() {
 }
 
 #[test]
+#[allow(clippy::drop_non_drop)]
 pub fn test_read_line() {
     let characters = ascii();
 
@@ -376,3 +390,3733 @@ pub fn test_write() {
         panic!("target != data");
     }
 }
+
+/// Returns at most the given amount of bytes per call until the data is exhausted.
+/// 1 splits every value across several reads.
+struct MaxChunkReader<'a>(&'a [u8], usize);
+
+impl Read for MaxChunkReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = buf.len().min(self.1).min(self.0.len());
+        buf[..count].copy_from_slice(&self.0[..count]);
+        self.0 = &self.0[count..];
+        Ok(count)
+    }
+}
+
+#[test]
+pub fn test_read_to_string_one_byte() {
+    let text = "ascii, ümlaut, 日本語, 🦀\n";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut str = String::new();
+    let n = buf
+        .read_to_string(&mut MaxChunkReader(text.as_bytes(), 1), &mut str)
+        .unwrap();
+    assert_eq!(n, text.len());
+    assert_eq!(str, text);
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+pub fn test_read_to_string_encoded() {
+    let text = "日本語のテキスト\nカタカナ and ascii\n";
+    let (data, _, unmappable) = encoding_rs::SHIFT_JIS.encode(text);
+    assert!(!unmappable);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut str = String::new();
+    let n = buf
+        .read_to_string_encoded(
+            &mut MaxChunkReader(&data, 1),
+            encoding_rs::SHIFT_JIS,
+            &mut str,
+        )
+        .unwrap();
+    assert_eq!(n, data.len());
+    assert_eq!(str, text);
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+pub fn test_read_to_string_encoded_invalid() {
+    //0x82 is a lead byte which is not followed by a trail byte before EOF.
+    let data = [b'a', 0x82];
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut str = String::new();
+    let n = buf
+        .read_to_string_encoded(
+            &mut MaxChunkReader(&data, 1),
+            encoding_rs::SHIFT_JIS,
+            &mut str,
+        )
+        .unwrap();
+    assert_eq!(n, 2);
+    assert_eq!(str, "a\u{FFFD}");
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+pub fn test_read_line_encoded() {
+    for encoding in [encoding_rs::SHIFT_JIS, encoding_rs::UTF_16LE] {
+        let text = "日本語のテキスト\nカタカナ\n最後";
+        let data: Vec<u8> = if encoding == encoding_rs::UTF_16LE {
+            text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+        } else {
+            encoding.encode(text).0.into_owned()
+        };
+
+        let mut reader = MaxChunkReader(&data, 1);
+        let mut buf = UnownedReadBuffer::<16>::new();
+        let mut total = 0;
+        for expected in ["日本語のテキスト\n", "カタカナ\n", "最後", ""] {
+            let mut str = String::new();
+            total += buf
+                .read_line_encoded(&mut reader, encoding, &mut str)
+                .unwrap();
+            assert_eq!(str, expected);
+        }
+        assert_eq!(total, data.len());
+    }
+}
+
+#[test]
+pub fn test_varint() {
+    let values = [
+        0u64,
+        1,
+        127,
+        128,
+        300,
+        0x3FFF,
+        0x4000,
+        u64::from(u32::MAX),
+        u64::MAX - 1,
+        u64::MAX,
+    ];
+    let signed = [
+        0i64,
+        1,
+        -1,
+        63,
+        -64,
+        64,
+        -65,
+        i64::MAX,
+        i64::MIN,
+        i64::MIN + 1,
+    ];
+
+    let mut data = Vec::new();
+    let mut write_buf = UnownedWriteBuffer::<16>::new();
+    for value in values {
+        write_buf.write_varint_u64(&mut data, value).unwrap();
+    }
+    for value in signed {
+        write_buf.write_varint_i64(&mut data, value).unwrap();
+    }
+    write_buf.flush(&mut data).unwrap();
+
+    assert_eq!(&data[..6], &[0, 1, 0x7F, 0x80, 0x01, 0xAC]);
+
+    //Every varint straddles a refill boundary.
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    for value in values {
+        assert_eq!(read_buf.read_varint_u64(&mut reader).unwrap(), value);
+    }
+    for value in signed {
+        assert_eq!(read_buf.read_varint_i64(&mut reader).unwrap(), value);
+    }
+    assert_eq!(
+        read_buf.read_varint_u64(&mut reader).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+pub fn test_varint_invalid() {
+    let overlong = [0xFFu8; 11];
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(&overlong, 1);
+    assert_eq!(
+        read_buf.read_varint_u64(&mut reader).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    //Nothing was consumed.
+    assert_eq!(read_buf.available(), 10);
+
+    let overflow = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02];
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        read_buf
+            .read_varint_u64(&mut Cursor::new(overflow))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidData
+    );
+
+    let truncated = [0x80, 0x80];
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        read_buf
+            .read_varint_u64(&mut Cursor::new(truncated))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert_eq!(read_buf.available(), 2);
+}
+
+#[test]
+pub fn test_varint_u32() {
+    let values = [0u32, 1, 127, 128, 300, 0x0FFF_FFFF, 0x1000_0000, u32::MAX];
+    let mut data = Vec::new();
+    let mut write_buf = UnownedWriteBuffer::<16>::new();
+    for value in values {
+        write_buf.write_varint_u32(&mut data, value).unwrap();
+    }
+    write_buf.flush(&mut data).unwrap();
+    assert_eq!(&data[data.len() - 5..], &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    for value in values {
+        assert_eq!(read_buf.read_varint_u32(&mut reader).unwrap(), value);
+    }
+    assert_eq!(
+        read_buf.read_varint_u32(&mut reader).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+
+    //u32 and u64 varints share the encoding.
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(&data);
+    for value in values {
+        assert_eq!(
+            read_buf.read_varint_u64(&mut cursor).unwrap(),
+            u64::from(value)
+        );
+    }
+
+    let overflow = [0xFF, 0xFF, 0xFF, 0xFF, 0x10];
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        read_buf
+            .read_varint_u32(&mut Cursor::new(overflow))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidData
+    );
+    //Nothing was consumed.
+    assert_eq!(read_buf.available(), 5);
+}
+
+#[test]
+pub fn test_varint_zigzag() {
+    let mut data = Vec::new();
+    let mut write_buf = UnownedWriteBuffer::<16>::new();
+    for value in [0i64, -1, 1, -2, 2] {
+        write_buf.write_varint_i64(&mut data, value).unwrap();
+    }
+    write_buf.flush(&mut data).unwrap();
+    //Zigzag interleaves negative and positive values.
+    assert_eq!(data, [0, 1, 2, 3, 4]);
+
+    let mut values = vec![0i64, 1, -1, i64::MAX, i64::MIN, i64::MIN + 1];
+    let mut values32 = vec![0i32, 1, -1, i32::MAX, i32::MIN, i32::MIN + 1];
+    for _ in 0..1000 {
+        values.push(random::<i64>() >> (random::<u32>() % 64));
+        values32.push(random::<i32>() >> (random::<u32>() % 32));
+    }
+
+    let mut data = Vec::new();
+    let mut write_buf = UnownedWriteBuffer::<16>::new();
+    for (value, value32) in values.iter().zip(&values32) {
+        write_buf.write_varint_i64(&mut data, *value).unwrap();
+        write_buf.write_varint_i32(&mut data, *value32).unwrap();
+    }
+    write_buf.flush(&mut data).unwrap();
+
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    for (value, value32) in values.iter().zip(&values32) {
+        assert_eq!(read_buf.read_varint_i64(&mut reader).unwrap(), *value);
+        assert_eq!(read_buf.read_varint_i32(&mut reader).unwrap(), *value32);
+    }
+
+    //i32 values are encoded the same as i64 values.
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    let mut encoded = Vec::new();
+    write_buf.write_varint_i32(&mut encoded, i32::MIN).unwrap();
+    write_buf.flush(&mut encoded).unwrap();
+    assert_eq!(
+        read_buf
+            .read_varint_i64(&mut Cursor::new(&encoded))
+            .unwrap(),
+        i64::from(i32::MIN)
+    );
+
+    let overlong = [0xFFu8; 11];
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        read_buf
+            .read_varint_i64(&mut Cursor::new(overlong))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidData
+    );
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        read_buf
+            .read_varint_i32(&mut Cursor::new(overlong))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidData
+    );
+    assert_eq!(read_buf.available(), 11);
+}
+
+#[test]
+pub fn test_read_char() {
+    let text = "a🦀b€ü🦀🦀\nz";
+    let mut reader = MaxChunkReader(text.as_bytes(), 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    for expected in text.chars() {
+        assert_eq!(buf.peek_char(&mut reader).unwrap(), Some(expected));
+        assert_eq!(buf.peek_char(&mut reader).unwrap(), Some(expected));
+        assert_eq!(buf.read_char(&mut reader).unwrap(), Some(expected));
+    }
+    assert_eq!(buf.peek_char(&mut reader).unwrap(), None);
+    assert_eq!(buf.read_char(&mut reader).unwrap(), None);
+}
+
+#[test]
+pub fn test_read_char_invalid() {
+    let data = [b'a', 0xF0, 0x9F, b'b', b'c'];
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.read_char(&mut reader).unwrap(), Some('a'));
+    assert_eq!(
+        buf.read_char(&mut reader).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    //The invalid bytes are still buffered.
+    let mut invalid = [0u8; 3];
+    assert_eq!(buf.try_read(&mut invalid), 3);
+    assert_eq!(invalid, [0xF0, 0x9F, b'b']);
+    assert_eq!(buf.read_char(&mut reader).unwrap(), Some('c'));
+
+    let data = [b'a', 0xF0, 0x9F, 0xA6];
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.read_char(&mut reader).unwrap(), Some('a'));
+    assert_eq!(
+        buf.read_char(&mut reader).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert_eq!(buf.available(), 3);
+}
+
+#[test]
+pub fn test_read_cstr() {
+    let data = b"\0hello\0a much longer string than the buffer\0tail";
+    let mut reader = Cursor::new(data);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    for expected in [&b""[..], b"hello", b"a much longer string than the buffer"] {
+        let mut str = Vec::new();
+        assert_eq!(
+            buf.read_cstr(&mut reader, &mut str).unwrap(),
+            expected.len()
+        );
+        assert_eq!(str, expected);
+    }
+
+    let mut str = Vec::new();
+    assert_eq!(
+        buf.read_cstr(&mut reader, &mut str).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert_eq!(str, b"tail");
+}
+
+#[test]
+pub fn test_chars() {
+    let mut data = "ab🦀\u{FF}€c".as_bytes().to_vec();
+    //Replace the 2 byte ÿ with 2 invalid bytes.
+    let invalid = data.iter().position(|b| *b == 0xC3).unwrap();
+    data[invalid] = 0xFF;
+    data[invalid + 1] = 0xFE;
+
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut result = String::new();
+    let mut errors = 0;
+    let mut chars = buf.chars(&mut reader);
+    while let Some(char) = chars.next() {
+        match char {
+            Ok(char) => result.push(char),
+            Err(e) => {
+                assert_eq!(e.kind(), ErrorKind::InvalidData);
+                errors += 1;
+                chars.consume(1);
+            }
+        }
+    }
+
+    assert_eq!(result, "ab🦀€c");
+    assert_eq!(errors, 2);
+
+    let mut reader = MaxChunkReader("x🦀y".as_bytes(), 1);
+    let mut borrowed = buf.borrow(&mut reader);
+    let collected: String = borrowed.chars().map(Result::unwrap).collect();
+    assert_eq!(collected, "x🦀y");
+}
+
+#[test]
+pub fn test_dyn_buffers() {
+    let mut data = vec![0u8; COUNT];
+    for j in data.iter_mut() {
+        *j = random()
+    }
+
+    let mut written = Vec::new();
+    let mut write_buf = DynWriteBuffer::with_capacity(0x10_0000);
+    assert_eq!(write_buf.size(), 0x10_0000);
+    let mut count = 0;
+    while count < data.len() {
+        let len = ((random::<usize>() % RAND_SIZE) + 1).min(data.len() - count);
+        write_buf
+            .write_all(&mut written, &data[count..count + len])
+            .unwrap();
+        count += len;
+    }
+    write_buf.flush(&mut written).unwrap();
+    assert_eq!(written, data);
+
+    let mut src_cursor = Cursor::new(&written);
+    let mut read_buf = DynReadBuffer::with_capacity(100);
+    assert_eq!(read_buf.size(), 100);
+    let mut target = Vec::new();
+    loop {
+        let mut cur_buf = vec![0u8; (random::<usize>() % RAND_SIZE) + 1];
+        let read = read_buf.read(&mut src_cursor, &mut cur_buf).unwrap();
+        if read == 0 {
+            break;
+        }
+        target.extend_from_slice(&cur_buf[..read]);
+    }
+    assert_eq!(target, data);
+}
+
+#[test]
+#[should_panic]
+pub fn test_dyn_buffer_too_small() {
+    let _ = DynReadBuffer::with_capacity(15);
+}
+
+#[test]
+pub fn test_invalid_utf8_error() {
+    let mut data = "héllo wörld, this is valid 🦀 utf-8".as_bytes().to_vec();
+    let invalid = data.len();
+    data.push(0xFF);
+    data.extend_from_slice(b" rest\n");
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut str = String::new();
+    let err = buf
+        .read_to_string(&mut Cursor::new(&data), &mut str)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let payload = err
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<InvalidUtf8Error>()
+        .unwrap();
+    assert_eq!(payload.valid_appended(), str.len());
+    assert_eq!(payload.valid_appended() + payload.invalid_offset(), invalid);
+    assert_eq!(buf.internal_buffer()[payload.invalid_offset()], 0xFF);
+    assert_eq!(buf.invalid_remainder()[0], 0xFF);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut str = String::new();
+    let err = buf
+        .read_line(&mut MaxChunkReader(&data, 1), &mut str)
+        .unwrap_err();
+    let payload = err
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<InvalidUtf8Error>()
+        .unwrap();
+    assert_eq!(payload.valid_appended(), str.len());
+    assert_eq!(payload.valid_appended() + payload.invalid_offset(), invalid);
+    assert_eq!(buf.invalid_remainder()[0], 0xFF);
+}
+
+#[test]
+pub fn test_read_line_multibyte() {
+    let mut data = Vec::new();
+    let chars = ['a', 'ü', '€', '🦀', '\n'];
+    for _ in 0..COUNT / 4 {
+        let mut tmp = [0u8; 4];
+        data.extend_from_slice(
+            chars[random::<usize>() % chars.len()]
+                .encode_utf8(&mut tmp)
+                .as_bytes(),
+        );
+    }
+    //Incomplete multibyte sequence at EOF.
+    data.extend_from_slice(b"\nend\xE2\x82");
+
+    let mut reader = Cursor::new(&data);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = Vec::new();
+    loop {
+        let mut str = String::new();
+        let n = buf.read_line(&mut reader, &mut str).unwrap();
+        assert_eq!(n, str.len());
+        if n == 0 {
+            break;
+        }
+        target.extend_from_slice(str.as_bytes());
+    }
+
+    assert_eq!(target, &data[..data.len() - 2]);
+    assert_eq!(buf.internal_buffer(), b"\xE2\x82");
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut str = String::new();
+    let err = buf
+        .read_to_string(&mut Cursor::new(&data), &mut str)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(str.as_bytes(), &data[..data.len() - 2]);
+    assert_eq!(buf.invalid_remainder(), b"\xE2\x82");
+}
+
+#[test]
+pub fn test_read_to_string_chunks() {
+    let text = "ascii, ümlaut, 日本語, 🦀\n".repeat(20);
+    let mut expected = String::new();
+    UnownedReadBuffer::<16>::new()
+        .read_to_string(&mut MaxChunkReader(text.as_bytes(), 1), &mut expected)
+        .unwrap();
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut str = String::new();
+    let mut chunks = 0;
+    let n = buf
+        .read_to_string_chunks(&mut MaxChunkReader(text.as_bytes(), 1), |chunk| {
+            assert!(chunk.len() <= 16);
+            chunks += 1;
+            str.push_str(chunk);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(n, text.len() as u64);
+    assert_eq!(str, expected);
+    assert!(chunks > 1);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(b"abc\xFFdef".to_vec());
+    let mut str = String::new();
+    let err = buf
+        .read_to_string_chunks(&mut cursor, |chunk| {
+            str.push_str(chunk);
+            Ok(())
+        })
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(str, "");
+    assert_eq!(buf.invalid_remainder(), b"\xFFdef");
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(b"abcdef".to_vec());
+    let err = buf
+        .read_to_string_chunks(&mut cursor, |_| Err(ErrorKind::Other.into()))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert_eq!(buf.internal_buffer(), b"abcdef");
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+pub fn test_zeroize() {
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(b"password".to_vec());
+    assert!(buf.ensure_readable(&mut cursor).unwrap());
+    buf.zeroize();
+    assert_eq!(buf.available(), 0);
+    assert!(buf.internal_buffer().is_empty());
+
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    let mut target = Vec::new();
+    buf.write_all(&mut target, b"password").unwrap();
+    buf.zeroize();
+    buf.flush(&mut target).unwrap();
+    assert!(target.is_empty());
+}
+
+#[test]
+pub fn test_eq() {
+    let mut a = UnownedReadBuffer::<16>::new();
+    let mut b = DynReadBuffer::with_capacity(32);
+    assert_eq!(a, b);
+    let mut cursor = Cursor::new(b"xabc".to_vec());
+    a.read_exact(&mut cursor, &mut [0u8; 1]).unwrap();
+    assert_eq!(a.available(), 3);
+    assert_ne!(a, b);
+    b.ensure_readable(&mut Cursor::new(b"abc".to_vec()))
+        .unwrap();
+    assert_eq!(a, b);
+
+    let mut a = UnownedWriteBuffer::<16>::new();
+    let mut b = UnownedWriteBuffer::<16>::new();
+    let mut target = Vec::new();
+    a.write_all(&mut target, b"stale").unwrap();
+    a.flush(&mut target).unwrap();
+    assert_eq!(a, b);
+    a.write_all(&mut target, b"abc").unwrap();
+    assert_ne!(a, b);
+    b.write_all(&mut target, b"abc").unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+pub fn test_read_exact_to_vec() {
+    let data: Vec<u8> = (0..1000u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data.clone());
+    let mut target = vec![0xAA];
+    buf.read_exact_to_vec(&mut cursor, 3, &mut target).unwrap();
+    assert_eq!(target, [0xAA, 0, 1, 2]);
+    assert!(buf.available() > 0);
+
+    buf.read_exact_to_vec(&mut cursor, 900, &mut target)
+        .unwrap();
+    assert_eq!(target[1..], data[..903]);
+
+    let mut one_byte = MaxChunkReader(&data[903..], 1);
+    buf.read_exact_to_vec(&mut one_byte, 50, &mut target)
+        .unwrap();
+    assert_eq!(target[1..], data[..953]);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = vec![0xAA];
+    let err = buf
+        .read_exact_to_vec(&mut Cursor::new(data.clone()), 1001, &mut target)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(target, [0xAA]);
+
+    let err = buf
+        .read_exact_to_vec(&mut MaxChunkReader(&data[..10], 1), 11, &mut target)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(target, [0xAA]);
+}
+
+#[test]
+pub fn test_clone() {
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(b"abcdef".to_vec());
+    buf.read_exact(&mut cursor, &mut [0u8; 1]).unwrap();
+    let mut snapshot = buf.clone();
+    let mut str = String::new();
+    buf.read_to_string(&mut cursor, &mut str).unwrap();
+    assert_eq!(str, "bcdef");
+    str.clear();
+    snapshot.read_to_string(&mut cursor, &mut str).unwrap();
+    assert_eq!(str, "bcdef");
+
+    let mut buf = DynWriteBuffer::with_capacity(16);
+    buf.write_all(&mut Vec::new(), b"abc").unwrap();
+    let mut snapshot = buf.clone();
+    let mut target = Vec::new();
+    buf.flush(&mut target).unwrap();
+    snapshot.flush(&mut target).unwrap();
+    assert_eq!(target, b"abcabc");
+}
+
+#[test]
+pub fn test_read_array() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(&data, 1);
+    assert_eq!(buf.read_array::<0, _>(&mut reader).unwrap(), [0u8; 0]);
+    assert_eq!(buf.read_array::<1, _>(&mut reader).unwrap(), [0]);
+    let array: [u8; 16] = buf.read_array(&mut reader).unwrap();
+    assert_eq!(array[..], data[1..17]);
+    let array: [u8; 55] = buf.read_array(&mut reader).unwrap();
+    assert_eq!(array[..], data[17..72]);
+    let err = buf.read_array::<29, _>(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(&data, 1);
+    assert_eq!(buf.peek_array::<0, _>(&mut reader).unwrap(), [0u8; 0]);
+    assert_eq!(buf.peek_array::<1, _>(&mut reader).unwrap(), [0]);
+    assert_eq!(buf.read_array::<1, _>(&mut reader).unwrap(), [0]);
+    let array: [u8; 16] = buf.peek_array(&mut reader).unwrap();
+    assert_eq!(array[..], data[1..17]);
+    let array: [u8; 16] = buf.read_array(&mut reader).unwrap();
+    assert_eq!(array[..], data[1..17]);
+    let err = buf.peek_array::<17, _>(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let mut reader = MaxChunkReader(&data[..3], 1);
+    let err = buf.peek_array::<4, _>(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(buf.internal_buffer(), &data[..3]);
+}
+
+#[test]
+pub fn test_read_nul_terminated() {
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = Vec::new();
+    let mut reader = MaxChunkReader(b"\0hello\0world\0toolong\0eof", 1);
+    assert_eq!(
+        buf.read_nul_terminated(&mut reader, 5, &mut target)
+            .unwrap(),
+        NulReadOutcome::Terminated(0)
+    );
+    assert!(target.is_empty());
+    assert_eq!(
+        buf.read_nul_terminated(&mut reader, 5, &mut target)
+            .unwrap(),
+        NulReadOutcome::Terminated(5)
+    );
+    assert_eq!(target, b"hello");
+    assert_eq!(
+        buf.read_cstring(&mut reader, 5).unwrap().as_bytes(),
+        b"world"
+    );
+    target.clear();
+    assert_eq!(
+        buf.read_nul_terminated(&mut reader, 5, &mut target)
+            .unwrap(),
+        NulReadOutcome::LimitReached
+    );
+    assert_eq!(target, b"toolo");
+    assert_eq!(buf.internal_buffer(), b"n");
+    target.clear();
+    assert_eq!(
+        buf.read_nul_terminated(&mut reader, 5, &mut target)
+            .unwrap(),
+        NulReadOutcome::Terminated(2)
+    );
+    assert_eq!(target, b"ng");
+    target.clear();
+    assert_eq!(
+        buf.read_nul_terminated(&mut reader, 5, &mut target)
+            .unwrap(),
+        NulReadOutcome::Eof(3)
+    );
+    assert_eq!(target, b"eof");
+
+    let mut cursor = Cursor::new(b"toolong\0".to_vec());
+    let err = buf.read_cstring(&mut cursor, 4).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let err = buf
+        .read_cstring(&mut Cursor::new(b"eof".to_vec()), 4)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    //usize::MAX means unlimited.
+    let mut reader = MaxChunkReader(b"a string that is longer than the buffer\0tail\0", 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    target.clear();
+    assert_eq!(
+        buf.read_nul_terminated(&mut reader, usize::MAX, &mut target)
+            .unwrap(),
+        NulReadOutcome::Terminated(39)
+    );
+    assert_eq!(target, b"a string that is longer than the buffer");
+    assert_eq!(
+        buf.read_cstring(&mut reader, usize::MAX)
+            .unwrap()
+            .as_bytes(),
+        b"tail"
+    );
+}
+
+#[test]
+pub fn test_new_boxed() {
+    let mut read_buf = UnownedReadBuffer::<0x800000>::new_boxed();
+    let mut write_buf = UnownedWriteBuffer::<0x800000>::new_boxed();
+    assert_eq!(read_buf.size(), 0x800000);
+    assert_eq!(read_buf.available(), 0);
+    assert_eq!(write_buf.available(), 0x800000);
+
+    let mut target = Vec::new();
+    write_buf.write_all(&mut target, b"hello").unwrap();
+    write_buf.flush(&mut target).unwrap();
+    let mut str = String::new();
+    read_buf
+        .read_to_string(&mut Cursor::new(target), &mut str)
+        .unwrap();
+    assert_eq!(str, "hello");
+}
+
+/// Returns the data in the given chunk sizes and then `ErrorKind::WouldBlock` the given amount of times before EOF.
+struct ChunkReader<'a>(&'a [u8], &'a [usize], usize);
+
+impl Read for ChunkReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some((first, rest)) = self.1.split_first() {
+            let count = (*first).min(buf.len()).min(self.0.len());
+            buf[..count].copy_from_slice(&self.0[..count]);
+            self.0 = &self.0[count..];
+            self.1 = rest;
+            return Ok(count);
+        }
+
+        if self.2 > 0 {
+            self.2 -= 1;
+            return Err(ErrorKind::WouldBlock.into());
+        }
+
+        Ok(0)
+    }
+}
+
+#[test]
+pub fn test_read_fill() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut target = [0u8; 40];
+    assert_eq!(buf.read_fill(&mut reader, &mut target).unwrap(), 40);
+    assert_eq!(target[..], data[..40]);
+    let mut target = [0u8; 80];
+    assert_eq!(buf.read_fill(&mut reader, &mut target).unwrap(), 60);
+    assert_eq!(target[..60], data[40..]);
+    assert_eq!(buf.read_fill(&mut reader, &mut target).unwrap(), 0);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = ChunkReader(&data, &[10, 10, 5], 2);
+    let mut target = [0u8; 40];
+    //The error is returned together with the progress, it is not dropped.
+    let (count, err) = buf.read_fill(&mut reader, &mut target).unwrap_err();
+    assert_eq!(count, 25);
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    assert_eq!(target[..25], data[..25]);
+    let (count, err) = buf.read_fill(&mut reader, &mut target[25..]).unwrap_err();
+    assert_eq!(count, 0);
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    assert_eq!(buf.read_fill(&mut reader, &mut target[25..]).unwrap(), 0);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = Read::chain(&data[..10], FailingReader);
+    let mut target = [0u8; 40];
+    let (count, err) = buf.read_fill(&mut reader, &mut target).unwrap_err();
+    assert_eq!(count, 10);
+    assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+}
+
+#[test]
+pub fn test_parts() {
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(b"abcdef".to_vec());
+    buf.read_exact(&mut cursor, &mut [0u8; 2]).unwrap();
+    let (array, read_count, fill_count) = buf.into_parts();
+    assert_eq!(&array[read_count..fill_count], b"cdef");
+    let buf = UnownedReadBuffer::from_parts(array, read_count, fill_count);
+    assert_eq!(buf.internal_buffer(), b"cdef");
+
+    let mut buf = DynReadBuffer::with_capacity(16);
+    buf.ensure_readable(&mut Cursor::new(b"abc".to_vec()))
+        .unwrap();
+    let (slice, read_count, fill_count) = buf.into_parts();
+    let buf = DynReadBuffer::from_parts(slice, read_count, fill_count);
+    assert_eq!(buf.internal_buffer(), b"abc");
+
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    buf.write_all(&mut Vec::new(), b"abc").unwrap();
+    let (array, fill_count) = buf.into_parts();
+    let mut buf = UnownedWriteBuffer::from_parts(array, fill_count);
+    let mut target = Vec::new();
+    buf.flush(&mut target).unwrap();
+    assert_eq!(target, b"abc");
+
+    let mut buf = DynWriteBuffer::with_capacity(16);
+    buf.write_all(&mut Vec::new(), b"abc").unwrap();
+    let (slice, fill_count) = buf.into_parts();
+    let mut buf = DynWriteBuffer::from_parts(slice, fill_count);
+    let mut target = Vec::new();
+    buf.flush(&mut target).unwrap();
+    assert_eq!(target, b"abc");
+}
+
+#[test]
+#[should_panic]
+pub fn test_from_parts_invalid() {
+    let _ = UnownedReadBuffer::from_parts([0u8; 16], 4, 3);
+}
+
+#[test]
+pub fn test_has_data_left() {
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = ChunkReader(b"abc", &[0, 3, 0], 1);
+    assert!(!buf.has_data_left(&mut reader).unwrap());
+    assert!(buf.has_data_left(&mut reader).unwrap());
+    assert!(buf.has_data_left(&mut reader).unwrap());
+    assert_eq!(buf.internal_buffer(), b"abc");
+    buf.consume(3);
+    assert!(!buf.has_data_left(&mut reader).unwrap());
+    let err = buf.has_data_left(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = ChunkReader(b"abc", &[3], 0);
+    let mut borrowed = buf.borrow(&mut reader);
+    assert!(borrowed.has_data_left().unwrap());
+    let mut str = String::new();
+    borrowed.read_to_string(&mut str).unwrap();
+    assert!(!borrowed.has_data_left().unwrap());
+}
+
+#[test]
+pub fn test_read_with() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data.clone());
+    let mut observed = Vec::new();
+    let mut target = Vec::new();
+    let mut chunk = [0u8; 7];
+    buf.read_exact_with(&mut cursor, &mut chunk, |b| observed.extend_from_slice(b))
+        .unwrap();
+    target.extend_from_slice(&chunk);
+    loop {
+        let count = buf
+            .read_with(&mut cursor, &mut chunk, |b| observed.extend_from_slice(b))
+            .unwrap();
+        if count == 0 {
+            break;
+        }
+        target.extend_from_slice(&chunk[..count]);
+    }
+
+    assert_eq!(target, data);
+    assert_eq!(observed, data);
+    let err = buf
+        .read_exact_with(&mut cursor, &mut chunk, |_| panic!("must not be called"))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+pub fn test_read_while() {
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(b"abc123def".to_vec());
+    let mut target = Vec::new();
+    assert_eq!(
+        buf.read_while(&mut cursor, |b| b.is_ascii_digit(), &mut target)
+            .unwrap(),
+        0
+    );
+    assert!(target.is_empty());
+    assert_eq!(buf.internal_buffer(), b"abc123def");
+    assert_eq!(
+        buf.read_while(&mut cursor, |b| b.is_ascii_alphabetic(), &mut target)
+            .unwrap(),
+        3
+    );
+    assert_eq!(target, b"abc");
+    assert_eq!(buf.internal_buffer(), b"123def");
+
+    //The predicate fails on the first byte of the second chunk.
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = ChunkReader(b"aaaabbbb1", &[4, 4, 1], 0);
+    let mut target = Vec::new();
+    assert_eq!(
+        buf.read_while(&mut reader, |b| b == b'a', &mut target)
+            .unwrap(),
+        4
+    );
+    assert_eq!(buf.internal_buffer(), b"bbbb");
+    assert_eq!(
+        buf.read_while(&mut reader, |b| b == b'b', &mut target)
+            .unwrap(),
+        4
+    );
+    assert_eq!(buf.internal_buffer(), b"1");
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let data = [b'x'; 100];
+    let mut target = Vec::new();
+    assert_eq!(
+        buf.read_while_limit(&mut MaxChunkReader(&data, 1), |_| true, 40, &mut target)
+            .unwrap(),
+        40
+    );
+    let mut reader = Cursor::new(data.to_vec());
+    assert_eq!(
+        buf.read_while(&mut reader, |_| true, &mut target).unwrap(),
+        100
+    );
+    assert_eq!(target.len(), 140);
+}
+
+#[test]
+pub fn test_read_token() {
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(
+        " \t12 日本語\r\n\n  -3\tlongtokenlongertheninternalbuffer  \n ".as_bytes(),
+        1,
+    );
+    let mut tokens = Vec::new();
+    loop {
+        let mut token = String::new();
+        let count = buf.read_token(&mut reader, &mut token).unwrap();
+        if count == 0 {
+            break;
+        }
+        assert_eq!(count, token.len());
+        tokens.push(token);
+    }
+    assert_eq!(
+        tokens,
+        ["12", "日本語", "-3", "longtokenlongertheninternalbuffer"]
+    );
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new("  日本語 abc".as_bytes().to_vec());
+    let mut token = String::new();
+    assert_eq!(buf.read_token_limit(&mut cursor, 4, &mut token).unwrap(), 3);
+    assert_eq!(token, "日");
+    token.clear();
+    assert_eq!(buf.read_token_limit(&mut cursor, 6, &mut token).unwrap(), 6);
+    assert_eq!(token, "本語");
+    token.clear();
+    assert_eq!(buf.read_token_limit(&mut cursor, 6, &mut token).unwrap(), 3);
+    assert_eq!(token, "abc");
+
+    //0 always means EOF, a limit that cannot hold the next character is an error.
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(" €x ".as_bytes().to_vec());
+    let mut token = String::new();
+    for limit in [0, 2] {
+        let err = buf
+            .read_token_limit(&mut cursor, limit, &mut token)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(buf.available(), 5);
+    }
+    assert_eq!(buf.read_token_limit(&mut cursor, 3, &mut token).unwrap(), 3);
+    assert_eq!(buf.read_token_limit(&mut cursor, 3, &mut token).unwrap(), 1);
+    assert_eq!(token, "€x");
+    assert_eq!(buf.read_token_limit(&mut cursor, 0, &mut token).unwrap(), 0);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(b" ab\xFFc d".to_vec());
+    let mut token = String::new();
+    let err = buf.read_token(&mut cursor, &mut token).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(buf.invalid_remainder(), b"\xFFc d");
+}
+
+#[test]
+pub fn test_read_exact_counted() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data.clone());
+    let mut target = [0u8; 30];
+    buf.read_exact_counted(&mut cursor, &mut target).unwrap();
+    assert_eq!(target[..], data[..30]);
+    let (count, err) = buf
+        .read_exact_counted(&mut cursor, &mut target)
+        .unwrap_err();
+    assert_eq!(count, 10);
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(target[..10], data[30..]);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = ChunkReader(&data, &[5, 5], 1);
+    let (count, err) = buf
+        .read_exact_counted(&mut reader, &mut target)
+        .unwrap_err();
+    assert_eq!(count, 10);
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+}
+
+#[test]
+pub fn test_read_ascii_number() {
+    let text = " 12 +34\n18446744073709551615 0000000000000000000000001 7x";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(text.as_bytes(), 1);
+    assert_eq!(buf.read_ascii_u64(&mut reader).unwrap(), 12);
+    assert_eq!(buf.read_ascii_u64(&mut reader).unwrap(), 34);
+    assert_eq!(buf.read_ascii_u64(&mut reader).unwrap(), u64::MAX);
+    assert_eq!(buf.read_ascii_u64(&mut reader).unwrap(), 1);
+    assert_eq!(buf.read_ascii_u64(&mut reader).unwrap(), 7);
+    assert_eq!(buf.internal_buffer(), b"x");
+    let err = buf.read_ascii_u64(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    buf.consume(1);
+    let err = buf.read_ascii_u64(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(b"18446744073709551616", 1);
+    let err = buf.read_ascii_u64(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let mut reader = MaxChunkReader(b"-1", 1);
+    let err = buf.read_ascii_u64(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let text = "-12 +9223372036854775807 -9223372036854775808 9223372036854775808 - ";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(text.as_bytes(), 1);
+    assert_eq!(buf.read_ascii_i64(&mut reader).unwrap(), -12);
+    assert_eq!(buf.read_ascii_i64(&mut reader).unwrap(), i64::MAX);
+    assert_eq!(buf.read_ascii_i64(&mut reader).unwrap(), i64::MIN);
+    let err = buf.read_ascii_i64(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    buf.consume(1);
+    let err = buf.read_ascii_i64(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+pub fn test_skip_exact() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(&data, 1);
+    buf.skip_exact(&mut reader, 0).unwrap();
+    buf.skip_exact(&mut reader, 3).unwrap();
+    assert_eq!(buf.read_array::<1, _>(&mut reader).unwrap(), [3]);
+    buf.skip_exact(&mut reader, 50).unwrap();
+    assert_eq!(buf.read_array::<1, _>(&mut reader).unwrap(), [54]);
+    let err = buf.skip_exact(&mut reader, 46).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+pub fn test_records() {
+    let data: Vec<u8> = (0..96u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data.clone());
+    let records = buf
+        .records::<32, _>(&mut cursor)
+        .collect::<std::io::Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records.concat(), data);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(&data[..95], 1);
+    let mut records = buf.records::<32, _>(&mut reader);
+    assert_eq!(records.next().unwrap().unwrap()[..], data[..32]);
+    assert_eq!(records.next().unwrap().unwrap()[..], data[32..64]);
+    let err = records.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert!(records.next().is_none());
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data.clone());
+    assert_eq!(buf.records::<8, _>(&mut cursor).count(), 12);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data.clone());
+    let records = buf
+        .records::<1, _>(&mut cursor)
+        .map(|record| record.unwrap()[0])
+        .collect::<Vec<_>>();
+    assert_eq!(records, data);
+}
+
+#[test]
+pub fn test_fill_buf_at_least() {
+    let data: Vec<u8> = (0..20u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = MaxChunkReader(&data, 1);
+    assert_eq!(buf.fill_buf_at_least(&mut reader, 4).unwrap(), &data[..4]);
+    buf.consume(2);
+    assert_eq!(
+        buf.fill_buf_at_least(&mut reader, 16).unwrap(),
+        &data[2..18]
+    );
+    buf.consume(10);
+    assert_eq!(buf.fill_buf_at_least(&mut reader, 16).unwrap(), &data[12..]);
+    let err = buf.fill_buf_at_least(&mut reader, 17).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+/// Counts the calls to read and the size of the largest buffer passed to it.
+struct CountingReader<R: Read>(R, usize, usize);
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.1 += 1;
+        self.2 = self.2.max(buf.len());
+        self.0.read(buf)
+    }
+}
+
+#[test]
+pub fn test_read_large_bypass() {
+    let data: Vec<u8> = (0..200u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = CountingReader(Cursor::new(data.clone()), 0, 0);
+    let mut target = [0u8; 64];
+    assert_eq!(buf.read(&mut reader, &mut target).unwrap(), 64);
+    assert_eq!(target[..], data[..64]);
+    assert_eq!(reader.1, 1);
+    assert_eq!(reader.2, 64);
+    assert_eq!(buf.available(), 0);
+
+    let mut small = [0u8; 4];
+    assert_eq!(buf.read(&mut reader, &mut small).unwrap(), 4);
+    assert_eq!(small[..], data[64..68]);
+    assert_eq!(buf.available(), 12);
+
+    //The internal buffer is not empty so it is drained first.
+    assert_eq!(buf.read(&mut reader, &mut target).unwrap(), 12);
+    assert_eq!(target[..12], data[68..80]);
+    assert_eq!(reader.1, 2);
+}
+
+#[test]
+pub fn test_mark_reset() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data.clone());
+    assert_eq!(buf.read_array::<2, _>(&mut cursor).unwrap(), [0, 1]);
+    let outer = buf.mark();
+    assert_eq!(buf.read_array::<2, _>(&mut cursor).unwrap(), [2, 3]);
+    let inner = buf.mark();
+    assert_eq!(buf.read_array::<2, _>(&mut cursor).unwrap(), [4, 5]);
+    buf.reset(inner).unwrap();
+    assert_eq!(buf.read_array::<2, _>(&mut cursor).unwrap(), [4, 5]);
+    buf.reset(outer).unwrap();
+    assert_eq!(buf.read_array::<4, _>(&mut cursor).unwrap(), [2, 3, 4, 5]);
+    //Rewinding to the inner mark after the outer mark was reset is also fine.
+    buf.reset(inner).unwrap();
+    assert_eq!(buf.read_array::<2, _>(&mut cursor).unwrap(), [4, 5]);
+
+    //Reading at most available() bytes keeps the mark valid, even if the slice is larger.
+    let mark = buf.mark();
+    let mut target = [0u8; 13];
+    assert_eq!(buf.read(&mut cursor, &mut target).unwrap(), 10);
+    buf.reset(mark).unwrap();
+    assert_eq!(buf.try_read(&mut target), 10);
+    buf.reset(mark).unwrap();
+
+    //Reading more than available refills the buffer and invalidates the mark.
+    let mark = buf.mark();
+    assert_eq!(buf.available(), 10);
+    assert_eq!(buf.read_array::<11, _>(&mut cursor).unwrap()[0], 6);
+    assert_eq!(buf.reset(mark), Err(MarkInvalidated));
+    assert_eq!(buf.read_array::<1, _>(&mut cursor).unwrap(), [17]);
+}
+
+/// Counts the calls to write and accepts at most the given amount of bytes per call.
+struct CountingWriter(Vec<u8>, usize, usize);
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.1 += 1;
+        self.0.write(&buf[..buf.len().min(self.2)])
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+pub fn test_write_all_large_bypass() {
+    let data: Vec<u8> = (0..1000u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    let mut writer = CountingWriter(Vec::new(), 0, usize::MAX);
+    buf.write_all(&mut writer, &data).unwrap();
+    assert_eq!(writer.1, 1);
+    assert_eq!(writer.0, data);
+
+    let mut writer = CountingWriter(Vec::new(), 0, usize::MAX);
+    buf.write_all(&mut writer, &data[..10]).unwrap();
+    buf.write_all(&mut writer, &data[10..]).unwrap();
+    buf.flush(&mut writer).unwrap();
+    //The first 16 bytes go through the internal buffer, the rest is written directly.
+    assert_eq!(writer.1, 2);
+    assert_eq!(writer.0, data);
+}
+
+#[test]
+pub fn test_owned_read_buffer() {
+    use std::io::BufRead;
+
+    fn lines<R: BufRead>(read: R) -> Vec<String> {
+        read.lines().map(Result::unwrap).collect()
+    }
+
+    let text = "first line\nsecond line that is longer than the buffer\nthird";
+    let buf = OwnedReadBuffer::<_, 16>::new(MaxChunkReader(text.as_bytes(), 1));
+    assert_eq!(
+        lines(buf),
+        [
+            "first line",
+            "second line that is longer than the buffer",
+            "third"
+        ]
+    );
+
+    let mut buf = OwnedReadBuffer::<_, 16>::new(Cursor::new(text.as_bytes().to_vec()));
+    let mut first = [0u8; 5];
+    buf.read_exact(&mut first).unwrap();
+    assert_eq!(&first, b"first");
+    assert_eq!(buf.get_ref().position(), 16);
+    buf.get_mut().set_position(0);
+    let cursor = buf.into_inner();
+    assert_eq!(cursor.position(), 0);
+}
+
+#[test]
+pub fn test_clear_and_truncate() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = CountingReader(Cursor::new(data.clone()), 0, 0);
+    assert_eq!(buf.clear(), 0);
+    buf.read_array::<4, _>(&mut reader).unwrap();
+    assert_eq!(buf.clear(), 12);
+    assert_eq!(buf.available(), 0);
+    assert_eq!(buf.read_array::<1, _>(&mut reader).unwrap(), [16]);
+    assert_eq!(reader.1, 2);
+
+    assert_eq!(buf.truncate_front(2), 2);
+    assert_eq!(buf.truncate_back(3), 3);
+    assert_eq!(buf.internal_buffer(), &data[19..29]);
+    assert_eq!(buf.truncate_back(100), 10);
+    assert_eq!(buf.truncate_front(1), 0);
+    assert_eq!(buf.read_array::<1, _>(&mut reader).unwrap(), [32]);
+}
+
+/// Fails every write after the first `self.1` bytes.
+#[derive(Debug)]
+struct FailingWriter(Vec<u8>, usize);
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let count = buf.len().min(self.1 - self.0.len());
+        if count == 0 {
+            return Err(ErrorKind::BrokenPipe.into());
+        }
+        self.0.extend_from_slice(&buf[..count]);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+pub fn test_owned_write_buffer() {
+    let mut target = Vec::new();
+    {
+        let mut buf = OwnedWriteBuffer::<_, 16>::new(&mut target);
+        buf.write_all(b"hello ").unwrap();
+        write!(buf, "{}", 42).unwrap();
+        assert!(buf.get_ref().is_empty());
+    }
+    assert_eq!(target, b"hello 42");
+
+    let mut buf = OwnedWriteBuffer::<_, 16>::new(Vec::new());
+    buf.write_all(b"abc").unwrap();
+    assert_eq!(buf.into_inner().unwrap(), b"abc");
+
+    let mut buf = OwnedWriteBuffer::<_, 16>::new(FailingWriter(Vec::new(), 2));
+    buf.write_all(b"abc").unwrap();
+    let err = buf.into_inner().unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::BrokenPipe);
+    let mut buf = err.into_inner();
+    assert_eq!(buf.get_ref().0, b"ab");
+    buf.get_mut().1 = 3;
+    assert_eq!(buf.into_inner().unwrap().0, b"abc");
+}
+
+#[test]
+pub fn test_owned_write_buffer_drop_error() {
+    use std::sync::{Arc, Mutex};
+
+    let dropped = Arc::new(Mutex::new(Vec::new()));
+    let mut buf = OwnedWriteBuffer::<_, 16>::new(FailingWriter(Vec::new(), 2));
+    let errors = dropped.clone();
+    assert!(buf
+        .set_drop_error_handler(move |err| errors.lock().unwrap().push(err.kind()))
+        .is_none());
+    buf.write_all(b"abc").unwrap();
+    drop(buf);
+    assert_eq!(*dropped.lock().unwrap(), [ErrorKind::BrokenPipe]);
+
+    //Successful flushes and removed handlers do not report anything.
+    let mut buf = OwnedWriteBuffer::<_, 16>::new(Vec::new());
+    let errors = dropped.clone();
+    buf.set_drop_error_handler(move |err| errors.lock().unwrap().push(err.kind()));
+    buf.write_all(b"abc").unwrap();
+    drop(buf);
+    let mut buf = OwnedWriteBuffer::<_, 16>::new(FailingWriter(Vec::new(), 0));
+    let errors = dropped.clone();
+    buf.set_drop_error_handler(move |err| errors.lock().unwrap().push(err.kind()));
+    assert!(buf.take_drop_error_handler().is_some());
+    buf.write_all(b"abc").unwrap();
+    drop(buf);
+    assert_eq!(dropped.lock().unwrap().len(), 1);
+}
+
+#[test]
+pub fn test_read_buffer_state() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data);
+    assert!(buf.is_empty());
+    assert_eq!(buf.len(), 0);
+    assert_eq!(buf.available_space(), 16);
+    assert_eq!(buf.total_free(), 16);
+    assert!(!buf.is_full());
+    assert!(buf.would_fit(16));
+    assert!(!buf.would_fit(17));
+
+    //full
+    assert!(buf.ensure_readable(&mut cursor).unwrap());
+    assert!(!buf.is_empty());
+    assert_eq!(buf.len(), 16);
+    assert_eq!(buf.available_space(), 0);
+    assert_eq!(buf.total_free(), 0);
+    assert!(buf.is_full());
+    assert!(buf.would_fit(0));
+    assert!(!buf.would_fit(1));
+
+    //partially read
+    buf.consume(6);
+    assert_eq!(buf.len(), 10);
+    assert_eq!(buf.available_space(), 0);
+    assert_eq!(buf.total_free(), 6);
+    assert!(!buf.is_full());
+    assert!(buf.would_fit(6));
+    assert!(!buf.would_fit(7));
+
+    //after compaction
+    assert_eq!(buf.fill_buf_at_least(&mut cursor, 12).unwrap().len(), 16);
+    assert_eq!(buf.len(), 16);
+    assert_eq!(buf.total_free(), 0);
+    assert!(buf.is_full());
+
+    //fully read
+    buf.consume(16);
+    assert!(buf.is_empty());
+    assert_eq!(buf.available_space(), 0);
+    assert_eq!(buf.total_free(), 16);
+    assert!(!buf.is_full());
+}
+
+#[test]
+pub fn test_take() {
+    use std::io::BufRead;
+
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new(data.clone());
+    assert!(buf.ensure_readable(&mut cursor).unwrap());
+
+    //limit smaller than what is buffered
+    let mut frame = Vec::new();
+    buf.take(&mut cursor, 5).read_to_end(&mut frame).unwrap();
+    assert_eq!(frame, data[..5]);
+    assert_eq!(buf.available(), 11);
+
+    //limit crossing a refill
+    let mut frame = Vec::new();
+    let mut limited = buf.take(&mut cursor, 30);
+    assert_eq!(limited.fill_buf().unwrap(), &data[5..16]);
+    limited.consume(3);
+    assert_eq!(limited.limit(), 27);
+    limited.read_to_end(&mut frame).unwrap();
+    assert_eq!(frame, data[8..35]);
+    assert_eq!(limited.limit(), 0);
+    assert!(limited.fill_buf().unwrap().is_empty());
+
+    //the parent buffer continues after the limit
+    let mut rest = Vec::new();
+    buf.read_to_end(&mut cursor, &mut rest).unwrap();
+    assert_eq!(rest, data[35..]);
+}
+
+#[test]
+pub fn test_bytes() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = CountingReader(Cursor::new(data.clone()), 0, 0);
+    let bytes = buf
+        .borrow(&mut reader)
+        .bytes()
+        .collect::<std::io::Result<Vec<u8>>>()
+        .unwrap();
+    assert_eq!(bytes, data);
+    //7 reads to fill the buffer and one more for EOF
+    assert_eq!(reader.1, 8);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut reader = ChunkReader(&data, &[2], 1);
+    let mut bytes = buf.borrow(&mut reader).bytes();
+    assert_eq!(bytes.next().unwrap().unwrap(), 0);
+    assert_eq!(bytes.next().unwrap().unwrap(), 1);
+    assert_eq!(
+        bytes.next().unwrap().unwrap_err().kind(),
+        ErrorKind::WouldBlock
+    );
+    assert!(bytes.next().is_none());
+}
+
+#[test]
+pub fn test_chain_frame_spans_readers() {
+    use std::io::BufRead;
+
+    let mut first: &[u8] = &[0, 6, b'a', b'b'];
+    let mut second = ChunkReader(b"cdefxyz\n", &[1, 2, 5], 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut chained = buf.chain(&mut first, &mut second);
+
+    let mut len = [0u8; 2];
+    chained.read_exact(&mut len).unwrap();
+    let mut payload = vec![0u8; u16::from_be_bytes(len) as usize];
+    chained.read_exact(&mut payload).unwrap();
+    assert_eq!(payload, b"abcdef");
+
+    let mut rest = Vec::new();
+    assert_eq!(chained.read_until(b'\n', &mut rest).unwrap(), 4);
+    assert_eq!(rest, b"xyz\n");
+    assert!(chained.fill_buf().unwrap().is_empty());
+}
+
+#[test]
+pub fn test_chain_fill_buf_switches_reader() {
+    use std::io::BufRead;
+
+    let mut first: &[u8] = b"ab";
+    let mut second: &[u8] = b"cd";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut chained = buf.chain(&mut first, &mut second);
+    assert_eq!(chained.fill_buf().unwrap(), b"ab");
+    chained.consume(2);
+    assert_eq!(chained.fill_buf().unwrap(), b"cd");
+    let mut rest = String::new();
+    chained.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, "cd");
+}
+
+#[test]
+pub fn test_tee_mirrors_consumed_bytes() {
+    use std::io::BufRead;
+
+    for _ in 0..64 {
+        let data: Vec<u8> = (0..RAND_SIZE).map(|_| random::<u8>() % 16 + b'a').collect();
+        let mut chunks = Vec::new();
+        let mut total = 0;
+        while total < data.len() {
+            let size = random::<usize>() % 40 + 1;
+            chunks.push(size);
+            total += size;
+        }
+
+        let mut reader = ChunkReader(&data, &chunks, 0);
+        let mut sink = Vec::new();
+        let mut observed = Vec::new();
+        let mut buf = UnownedReadBuffer::<32>::new();
+        let mut lost = false;
+        let mut tee = buf.tee(&mut reader, &mut sink);
+        loop {
+            match random::<u8>() % 4 {
+                0 => {
+                    let mut chunk = vec![0u8; random::<usize>() % 50];
+                    let count = tee.read(&mut chunk).unwrap();
+                    observed.extend_from_slice(&chunk[..count]);
+                    if count == 0 && !chunk.is_empty() {
+                        break;
+                    }
+                }
+                1 => {
+                    if tee.read_until(b'a', &mut observed).unwrap() == 0 {
+                        break;
+                    }
+                }
+                2 => {
+                    let available = tee.fill_buf().unwrap();
+                    if available.is_empty() {
+                        break;
+                    }
+                    let count = random::<usize>() % available.len() + 1;
+                    observed.extend_from_slice(&available[..count]);
+                    tee.consume(count);
+                }
+                _ => {
+                    let mut chunk = [0u8; 3];
+                    if tee.read_exact(&mut chunk).is_err() {
+                        // read_exact consumed up to 2 bytes that the caller never got to see.
+                        lost = true;
+                        break;
+                    }
+                    observed.extend_from_slice(&chunk);
+                }
+            }
+
+            assert!(tee.take_sink_error().is_none());
+        }
+        drop(tee);
+
+        if lost {
+            assert!(sink.len() - observed.len() < 3);
+            sink.truncate(observed.len());
+        }
+        assert_eq!(sink, observed);
+        assert_eq!(observed, data[..observed.len()]);
+    }
+}
+
+#[test]
+pub fn test_read_tee_sink_error() {
+    use std::io::BufRead;
+
+    let mut reader: &[u8] = b"abcdefgh";
+    let mut sink = FailingWriter(Vec::new(), 4);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = [0u8; 3];
+    assert_eq!(
+        buf.read_tee(&mut reader, &mut sink, &mut target).unwrap(),
+        3
+    );
+    let err = buf
+        .read_tee(&mut reader, &mut sink, &mut target)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    assert_eq!(buf.fill_buf(&mut reader).unwrap(), b"defgh");
+
+    let mut sink = FailingWriter(Vec::new(), 2);
+    let mut tee = buf.tee(&mut reader, &mut sink).ignore_sink_errors(true);
+    let mut rest = Vec::new();
+    tee.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"defgh");
+    assert_eq!(tee.take_sink_error().unwrap().kind(), ErrorKind::BrokenPipe);
+    drop(tee);
+    assert_eq!(sink.0, b"de");
+
+    let mut reader: &[u8] = b"abcdefgh";
+    let mut sink = FailingWriter(Vec::new(), 0);
+    let mut tee = buf.tee(&mut reader, &mut sink);
+    tee.fill_buf().unwrap();
+    tee.consume(2);
+    assert_eq!(tee.fill_buf().unwrap_err().kind(), ErrorKind::BrokenPipe);
+    assert_eq!(tee.fill_buf().unwrap(), b"cdefgh");
+}
+
+#[test]
+pub fn test_write_array() {
+    let mut target = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    buf.write_array(&mut target, 0x1234u16.to_be_bytes())
+        .unwrap();
+    buf.write_array(&mut target, [7u8; 20]).unwrap();
+    buf.flush(&mut target).unwrap();
+    let mut expected = vec![0x12, 0x34];
+    expected.extend_from_slice(&[7u8; 20]);
+    assert_eq!(target, expected);
+}
+
+#[test]
+#[allow(clippy::drop_non_drop)]
+pub fn test_observe_read_every_method() {
+    use std::io::BufRead;
+
+    let data: Vec<u8> = (0..RAND_SIZE).map(|_| random::<u8>() % 16 + b'a').collect();
+    let mut reader = data.as_slice();
+    let mut seen = Vec::new();
+    let mut buf = UnownedReadBuffer::<32>::new();
+    let mut observed = buf.observe(&mut reader, |bytes: &[u8]| seen.extend_from_slice(bytes));
+    let mut chunk = [0u8; 5];
+    observed.read_exact(&mut chunk).unwrap();
+    let count = observed.read(&mut chunk).unwrap();
+    assert!(count > 0);
+    let mut line = Vec::new();
+    observed.read_until(b'a', &mut line).unwrap();
+    let available = observed.fill_buf().unwrap().len();
+    observed.consume(available / 2);
+    let mut rest = Vec::new();
+    observed.read_to_end(&mut rest).unwrap();
+    drop(observed);
+
+    assert_eq!(seen, data);
+}
+
+#[test]
+#[allow(clippy::drop_non_drop)]
+pub fn test_observe_write_every_method() {
+    let data: Vec<u8> = (0..RAND_SIZE).map(|_| random::<u8>()).collect();
+    let mut target = Vec::new();
+    let mut seen = Vec::new();
+    let mut buf = UnownedWriteBuffer::<32>::new();
+    let mut observed = buf.observe(&mut target, |bytes: &[u8]| seen.extend_from_slice(bytes));
+    let mut remaining = data.as_slice();
+    while !remaining.is_empty() {
+        let size = (random::<usize>() % 80).min(remaining.len());
+        let (head, tail) = remaining.split_at(size);
+        if random::<bool>() {
+            observed.write_all(head).unwrap();
+            remaining = tail;
+        } else {
+            let count = observed.write(head).unwrap();
+            remaining = &remaining[count..];
+        }
+    }
+    observed.flush().unwrap();
+    drop(observed);
+
+    assert_eq!(seen, data);
+    assert_eq!(target, data);
+}
+
+#[test]
+#[allow(clippy::drop_non_drop)]
+pub fn test_observe_write_partial() {
+    let mut target = FailingWriter(Vec::new(), 10);
+    let mut seen = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    let mut observed = buf.observe(&mut target, |bytes: &[u8]| seen.extend_from_slice(bytes));
+    observed.write_all(b"01234567").unwrap();
+    observed.write_all(b"89abcdef").unwrap();
+    assert_eq!(observed.flush().unwrap_err().kind(), ErrorKind::BrokenPipe);
+    drop(observed);
+    assert_eq!(seen, b"0123456789");
+}
+
+/// Returns at most the given amount of bytes and the given error alternately.
+/// The error comes first if the flag is set.
+struct AlternatingErrorReader<'a>(&'a [u8], usize, ErrorKind, bool);
+
+impl Read for AlternatingErrorReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.3 = !self.3;
+        if !self.3 {
+            return Err(self.2.into());
+        }
+
+        let count = buf.len().min(self.1).min(self.0.len());
+        buf[..count].copy_from_slice(&self.0[..count]);
+        self.0 = &self.0[count..];
+        Ok(count)
+    }
+}
+
+#[test]
+pub fn test_read_exact_deadline() {
+    use std::time::{Duration, Instant};
+
+    let data = b"0123456789abcdef";
+    let mut reader = AlternatingErrorReader(data, 3, ErrorKind::WouldBlock, false);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = [0u8; 10];
+    let err = buf
+        .read_exact_deadline(&mut reader, &mut target, Instant::now())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    assert_eq!(&target[..3], b"012");
+
+    let mut reader = AlternatingErrorReader(data, 3, ErrorKind::WouldBlock, false);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    buf.read_exact_deadline(&mut reader, &mut target, deadline)
+        .unwrap();
+    assert_eq!(&target, b"0123456789");
+    buf.read_exact_deadline(&mut reader, &mut target[..6], deadline)
+        .unwrap();
+    assert_eq!(&target[..6], b"abcdef");
+    let err = buf
+        .read_exact_deadline(&mut reader, &mut target, deadline)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+/// Announces every call on the first channel and then blocks until the chunk arrives on the second.
+struct ChannelReader(
+    std::sync::mpsc::Sender<()>,
+    std::sync::mpsc::Receiver<Vec<u8>>,
+);
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.send(()).unwrap();
+        let chunk = self.1.recv().unwrap();
+        buf[..chunk.len()].copy_from_slice(&chunk);
+        Ok(chunk.len())
+    }
+}
+
+#[test]
+pub fn test_read_exact_cancellable() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::channel;
+
+    let cancel = AtomicBool::new(false);
+    let (call_tx, call_rx) = channel();
+    let (chunk_tx, chunk_rx) = channel();
+    let mut target = [0u8; 9];
+    std::thread::scope(|scope| {
+        let reader = scope.spawn(|| {
+            let mut reader = ChannelReader(call_tx, chunk_rx);
+            let mut buf = UnownedReadBuffer::<16>::new();
+            buf.read_exact_cancellable(&mut reader, &mut target, &cancel)
+        });
+
+        call_rx.recv().unwrap();
+        chunk_tx.send(b"abc".to_vec()).unwrap();
+        call_rx.recv().unwrap();
+        cancel.store(true, Ordering::Relaxed);
+        chunk_tx.send(b"def".to_vec()).unwrap();
+
+        let err = reader.join().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+    });
+    assert_eq!(&target, b"abcdef\0\0\0");
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut data: &[u8] = b"0123456789";
+    let mut all = Vec::new();
+    assert_eq!(
+        buf.read_to_end_cancellable(&mut data, &mut all, &cancel)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::Interrupted
+    );
+    cancel.store(false, Ordering::Relaxed);
+    assert_eq!(
+        buf.read_to_end_cancellable(&mut data, &mut all, &cancel)
+            .unwrap(),
+        10
+    );
+    assert_eq!(all, b"0123456789");
+}
+
+#[test]
+pub fn test_write_all_cancellable() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let cancel = AtomicBool::new(true);
+    let mut target = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    buf.write_all_cancellable(&mut target, b"0123", &cancel)
+        .unwrap();
+    let err = buf
+        .write_all_cancellable(&mut target, b"456789abcdefghijklmnop", &cancel)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Interrupted);
+    assert_eq!(
+        buf.flush_cancellable(&mut target, &cancel)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::Interrupted
+    );
+    assert!(target.is_empty());
+
+    cancel.store(false, Ordering::Relaxed);
+    buf.flush_cancellable(&mut target, &cancel).unwrap();
+    assert_eq!(&target[..4], b"0123");
+
+    cancel.store(true, Ordering::Relaxed);
+    let mut target = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    let err = buf
+        .write_all_cancellable(&mut target, &[0u8; 64], &cancel)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Interrupted);
+    assert!(target.is_empty());
+}
+
+#[test]
+pub fn test_copy_into_internal_buffer() {
+    let mut reader: &[u8] = b"0123456789abcdef";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut head = [0u8; 4];
+    buf.read_exact(&mut reader, &mut head).unwrap();
+    assert_eq!(buf.try_copy_into_internal_buffer(b"0123456789ab"), Err(4));
+    assert_eq!(buf.internal_buffer(), b"456789abcdef");
+    buf.copy_into_internal_buffer(b"XYZW");
+    assert_eq!(buf.internal_buffer(), b"456789abcdefXYZW");
+    assert_eq!(buf.try_copy_into_internal_buffer(b"!"), Err(0));
+    assert_eq!(buf.try_copy_into_internal_buffer(b""), Ok(()));
+}
+
+#[test]
+#[should_panic(expected = "do not fit")]
+pub fn test_copy_into_internal_buffer_panics() {
+    let mut buf = UnownedReadBuffer::<16>::new();
+    buf.copy_into_internal_buffer(&[0u8; 17]);
+}
+
+/// Accepts at most the given amount of bytes per call and returns `WouldBlock` when the budget is used up.
+struct WouldBlockWriter(Vec<u8>, Vec<usize>);
+
+impl Write for WouldBlockWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let Some(budget) = self.1.pop() else {
+            return Err(ErrorKind::WouldBlock.into());
+        };
+        let count = budget.min(buf.len());
+        self.0.extend_from_slice(&buf[..count]);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        panic!("try_flush must not call flush");
+    }
+}
+
+#[test]
+pub fn test_try_flush() {
+    let mut target = WouldBlockWriter(Vec::new(), vec![0, 100, 5]);
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    assert_eq!(buf.try_flush(&mut target).unwrap(), 0);
+    buf.write_all(&mut target, b"0123456789").unwrap();
+    assert_eq!(buf.try_flush(&mut target).unwrap(), 5);
+    assert_eq!(target.0, b"01234");
+    buf.write_all(&mut target, b"abc").unwrap();
+    assert_eq!(buf.try_flush(&mut target).unwrap(), 8);
+    assert_eq!(target.0, b"0123456789abc");
+    buf.write_all(&mut target, b"xyz").unwrap();
+    assert_eq!(
+        buf.try_flush(&mut target).unwrap_err().kind(),
+        ErrorKind::WriteZero
+    );
+    assert_eq!(
+        buf.try_flush(&mut target).unwrap_err().kind(),
+        ErrorKind::WouldBlock
+    );
+    assert_eq!(target.0, b"0123456789abc");
+}
+
+#[test]
+pub fn test_progress() {
+    let size = if cfg!(miri) { 0x1000 } else { 10 << 20 };
+    let data: Vec<u8> = (0..size).map(|n: usize| n.to_le_bytes()[0]).collect();
+
+    let mut reports = Vec::new();
+    let mut buf = UnownedReadBuffer::<4096>::new();
+    let mut cursor = Cursor::new(&data);
+    let mut head = [0u8; 10];
+    buf.read_exact(&mut cursor, &mut head).unwrap();
+    let mut all = Vec::new();
+    let count = buf
+        .read_to_end_with_progress(&mut cursor, &mut all, |n| reports.push(n))
+        .unwrap();
+    assert_eq!(count, size - 10);
+    assert!(reports.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(*reports.last().unwrap(), (size - 10) as u64);
+    assert!(reports.len() > 1);
+
+    let mut reports = Vec::new();
+    let mut cursor = Cursor::new(&data);
+    let mut target = vec![0u8; size];
+    buf.read_exact_with_progress(&mut cursor, &mut target, |n| reports.push(n))
+        .unwrap();
+    assert_eq!(target, data);
+    assert!(reports.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(*reports.last().unwrap(), size as u64);
+
+    let mut reports = Vec::new();
+    let mut cursor = Cursor::new(b"abc");
+    let mut text = String::new();
+    buf.read_to_string_with_progress(&mut cursor, &mut text, |n| reports.push(n))
+        .unwrap();
+    assert_eq!(reports, [3]);
+    buf.read_to_string_with_progress(&mut cursor, &mut text, |n| reports.push(n))
+        .unwrap();
+    buf.read_exact_with_progress(&mut cursor, &mut [], |n| reports.push(n))
+        .unwrap();
+    assert_eq!(reports, [3]);
+
+    let mut reports = Vec::new();
+    let mut target = Vec::new();
+    let mut buf = UnownedWriteBuffer::<4096>::new();
+    buf.write_all(&mut target, b"pre").unwrap();
+    buf.write_all_with_progress(&mut target, &data, |n| reports.push(n))
+        .unwrap();
+    assert!(reports.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(*reports.last().unwrap(), size as u64);
+
+    let mut reports = Vec::new();
+    buf.write_all_with_progress(&mut target, b"post", |n| reports.push(n))
+        .unwrap();
+    assert_eq!(reports, [4]);
+    let mut reports = Vec::new();
+    let buffered = (buf.size() - buf.available()) as u64;
+    buf.flush_with_progress(&mut target, |n| reports.push(n))
+        .unwrap();
+    assert_eq!(reports, [buffered]);
+    buf.flush_with_progress(&mut target, |n| reports.push(n))
+        .unwrap();
+    assert_eq!(reports, [buffered]);
+    assert_eq!(target.len(), size + 7);
+}
+
+#[test]
+pub fn test_read_exact_budgeted() {
+    let data = b"0123456789";
+    let mut reader = CountingReader(MaxChunkReader(data, 1), 0, 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = [0u8; 8];
+    assert_eq!(
+        buf.read_exact_budgeted(&mut reader, &mut target, 3)
+            .unwrap(),
+        Budgeted::Exhausted(3)
+    );
+    assert_eq!(reader.1, 3);
+    assert_eq!(
+        buf.read_exact_budgeted(&mut reader, &mut target[3..], 0)
+            .unwrap(),
+        Budgeted::Exhausted(0)
+    );
+    assert_eq!(reader.1, 3);
+    assert_eq!(
+        buf.read_exact_budgeted(&mut reader, &mut target[3..], 10)
+            .unwrap(),
+        Budgeted::Complete
+    );
+    assert_eq!(reader.1, 8);
+    assert_eq!(&target, b"01234567");
+    let err = buf
+        .read_exact_budgeted(&mut reader, &mut target, 10)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+pub fn test_write_all_budgeted() {
+    let data = b"0123456789abcdefghijklmnop";
+    let mut target = CountingWriter(Vec::new(), 0, 1);
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    assert_eq!(
+        buf.write_all_budgeted(&mut target, data, 4).unwrap(),
+        Budgeted::Exhausted(20)
+    );
+    assert_eq!(target.1, 4);
+    assert_eq!(target.0, b"0123");
+    assert_eq!(
+        buf.write_all_budgeted(&mut target, &data[20..], 0).unwrap(),
+        Budgeted::Exhausted(0)
+    );
+    assert_eq!(
+        buf.write_all_budgeted(&mut target, &data[20..], 6).unwrap(),
+        Budgeted::Complete
+    );
+    assert_eq!(target.1, 10);
+    buf.flush(&mut target).unwrap();
+    assert_eq!(target.0, data);
+}
+
+#[test]
+pub fn test_flush_counted() {
+    let mut target = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    assert_eq!(buf.flush_counted(&mut target).unwrap(), 0);
+    buf.write_all(&mut target, b"hello").unwrap();
+    assert_eq!(buf.flush_counted(&mut target).unwrap(), 5);
+    assert_eq!(buf.flush_counted(&mut target).unwrap(), 0);
+    assert_eq!(target, b"hello");
+}
+
+#[test]
+pub fn test_poll_readable() {
+    let mut reader = ChunkReader(b"abcdef", &[], 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::NotReady);
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::Eof);
+
+    let mut reader = AlternatingErrorReader(b"abcdef", 3, ErrorKind::WouldBlock, true);
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::NotReady);
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::Ready(3));
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::Ready(3));
+    buf.consume(3);
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::NotReady);
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::Ready(3));
+    buf.consume(3);
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::NotReady);
+    assert_eq!(buf.poll_readable(&mut reader).unwrap(), ReadState::Eof);
+
+    let mut reader = FailingReader;
+    assert_eq!(
+        buf.poll_readable(&mut reader).unwrap_err().kind(),
+        ErrorKind::ConnectionReset
+    );
+}
+
+/// Always fails with `ConnectionReset`.
+struct FailingReader;
+
+impl Read for FailingReader {
+    fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+        Err(ErrorKind::ConnectionReset.into())
+    }
+}
+
+#[test]
+pub fn test_ensure_writable() {
+    let mut target = CountingWriter(Vec::new(), 0, usize::MAX);
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    buf.write_all(&mut target, b"0123456789").unwrap();
+    assert_eq!(buf.ensure_writable(&mut target, 6).unwrap(), 6);
+    assert_eq!(target.1, 0);
+    assert_eq!(buf.ensure_writable(&mut target, 7).unwrap(), 16);
+    assert_eq!(target.1, 1);
+    assert_eq!(target.0, b"0123456789");
+    buf.write_all(&mut target, b"ab").unwrap();
+    assert_eq!(buf.ensure_writable(&mut target, 100).unwrap(), 16);
+    assert_eq!(buf.ensure_writable(&mut target, 100).unwrap(), 16);
+    assert_eq!(target.1, 2);
+    assert_eq!(target.0, b"0123456789ab");
+}
+
+#[test]
+pub fn test_transfer_from() {
+    let mut reader: &[u8] = b"HELLO0123456789abcdefghij";
+    let mut small = UnownedReadBuffer::<16>::new();
+    let mut hello = [0u8; 5];
+    small.read_exact(&mut reader, &mut hello).unwrap();
+    assert_eq!(small.internal_buffer(), b"0123456789a");
+
+    let mut large = UnownedReadBuffer::<64>::new();
+    assert_eq!(large.transfer_from(&mut small), 11);
+    assert!(small.is_empty());
+    let mut rest = Vec::new();
+    large.read_to_end(&mut reader, &mut rest).unwrap();
+    assert_eq!(rest, b"0123456789abcdefghij");
+
+    let mut other = DynReadBuffer::with_capacity(32);
+    let mut reader: &[u8] = b"0123456789abcdefghijklmnopqrstuv";
+    other.fill_buf(&mut reader).unwrap();
+    let mut target = UnownedReadBuffer::<16>::new();
+    let mut first = [0u8; 2];
+    let mut empty: &[u8] = b"xyz";
+    target.read_exact(&mut empty, &mut first).unwrap();
+    assert_eq!(target.transfer_from(&mut other), 15);
+    assert_eq!(target.internal_buffer(), b"z0123456789abcde");
+    assert_eq!(other.internal_buffer(), b"fghijklmnopqrstuv");
+}
+
+/// Returns `WouldBlock` once when the given offset of the data is reached.
+struct BlockAtReader<'a>(&'a [u8], usize, bool);
+
+impl Read for BlockAtReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.1 == 0 && !self.2 {
+            self.2 = true;
+            return Err(ErrorKind::WouldBlock.into());
+        }
+
+        let limit = if self.2 { self.0.len() } else { self.1 };
+        let count = buf.len().min(limit);
+        buf[..count].copy_from_slice(&self.0[..count]);
+        self.0 = &self.0[count..];
+        self.1 = self.1.saturating_sub(count);
+        Ok(count)
+    }
+}
+
+#[test]
+pub fn test_resume_read_exact() {
+    let size = if cfg!(miri) { 64 } else { 1024 };
+    let data: Vec<u8> = (0..size + 3).map(|n: usize| n.to_le_bytes()[0]).collect();
+    for offset in 0..size {
+        let mut reader = BlockAtReader(&data, offset, false);
+        let mut buf = UnownedReadBuffer::<16>::new();
+        let mut target = vec![0u8; size];
+        let mut state = ReadExactState::new(size);
+        let mut blocked = 0;
+        while !buf
+            .resume_read_exact(&mut reader, &mut state, &mut target)
+            .unwrap()
+        {
+            blocked += 1;
+            assert_eq!(state.filled(), offset);
+            assert_eq!(target[..state.filled()], data[..state.filled()]);
+        }
+        assert!(state.is_complete());
+        assert_eq!(blocked, 1);
+        assert_eq!(target, data[..size]);
+        let mut rest = Vec::new();
+        buf.read_to_end(&mut reader, &mut rest).unwrap();
+        assert_eq!(rest, data[size..]);
+    }
+
+    let mut reader: &[u8] = b"abc";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = [0u8; 4];
+    let mut state = ReadExactState::new(4);
+    let err = buf
+        .resume_read_exact(&mut reader, &mut state, &mut target)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(state.filled(), 3);
+}
+
+#[test]
+pub fn test_peek_until() {
+    let mut reader = MaxChunkReader(b"GET / HTTP/1.1\r\nHost: x\r\n", 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.peek_until(&mut reader, b'\n', 100).unwrap(), Some(15));
+    assert_eq!(buf.internal_buffer(), b"GET / HTTP/1.1\r\n");
+    assert_eq!(buf.peek_until(&mut reader, b'\n', 10).unwrap(), None);
+    buf.consume(16);
+    assert_eq!(buf.peek_until(&mut reader, b'\n', 100).unwrap(), Some(8));
+    buf.consume(9);
+    assert_eq!(buf.peek_until(&mut reader, b'\n', 100).unwrap(), None);
+
+    let mut reader: &[u8] = b"0123456789abcdefghij\n";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.peek_until(&mut reader, b'\n', 100).unwrap(), None);
+    assert_eq!(buf.available(), 16);
+}
+
+/// Records whether `read_to_end` was called directly.
+struct ReadToEndSpy<'a>(&'a [u8], bool);
+
+impl Read for ReadToEndSpy<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.1 = true;
+        self.0.read_to_end(buf)
+    }
+}
+
+#[test]
+pub fn test_read_exact_and_read_to_end_direct() {
+    let data: Vec<u8> = (0..1000u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = CountingReader(Cursor::new(data.clone()), 0, 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut head = [0u8; 4];
+    buf.read_exact(&mut reader, &mut head).unwrap();
+    let mut target = vec![0u8; 900];
+    buf.read_exact(&mut reader, &mut target).unwrap();
+    assert_eq!(target, data[4..904]);
+    assert_eq!(reader.1, 2);
+    assert_eq!(reader.2, 900 - 12);
+
+    let mut reader = ReadToEndSpy(&data, false);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    buf.read_exact(&mut reader, &mut head).unwrap();
+    let mut rest = Vec::new();
+    assert_eq!(buf.read_to_end(&mut reader, &mut rest).unwrap(), 996);
+    assert!(reader.1);
+    assert_eq!(rest, data[4..]);
+    assert!(buf.is_empty());
+
+    let mut reader: &[u8] = &data[..100];
+    let mut target = vec![0u8; 200];
+    let err = buf.read_exact(&mut reader, &mut target).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+pub fn test_has_complete_line() {
+    let mut reader: &[u8] = b"abc\ndef";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert!(!buf.has_complete_line());
+    buf.fill_buf(&mut reader).unwrap();
+    assert!(buf.has_complete_line());
+    buf.consume(4);
+    assert!(!buf.has_complete_line());
+}
+
+#[test]
+pub fn test_compaction_threshold() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    for (threshold, feeds) in [(None, 11), (Some(0), 14)] {
+        let mut buf = UnownedReadBuffer::<16>::new();
+        assert_eq!(buf.compaction_threshold(), 4);
+        if let Some(threshold) = threshold {
+            buf.set_compaction_threshold(threshold);
+            assert_eq!(buf.compaction_threshold(), 1);
+        }
+
+        let mut reader = MaxChunkReader(&data, 1);
+        buf.fill_buf_at_least(&mut reader, 2).unwrap();
+        buf.consume(1);
+        let mark = buf.mark();
+        for _ in 0..feeds {
+            let min = buf.available() + 1;
+            buf.fill_buf_at_least(&mut reader, min).unwrap();
+        }
+        //Every read went to the free end of the internal buffer, nothing was moved yet.
+        buf.reset(mark).unwrap();
+        assert_eq!(buf.internal_buffer(), &data[1..=feeds + 1]);
+
+        let min = buf.available() + 1;
+        assert_eq!(
+            buf.fill_buf_at_least(&mut reader, min).unwrap(),
+            &data[1..=feeds + 2]
+        );
+        assert!(buf.reset(mark).is_err());
+    }
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    buf.set_compaction_threshold(8);
+    let mut reader: &[u8] = &data;
+    let mut chunk = [0u8; 5];
+    let mut all = Vec::new();
+    loop {
+        let count = buf.read(&mut reader, &mut chunk).unwrap();
+        if count == 0 {
+            break;
+        }
+        all.extend_from_slice(&chunk[..count]);
+    }
+    assert_eq!(all, data);
+}
+
+#[test]
+pub fn test_read_to_end_with_hint() {
+    let data: Vec<u8> = (0..1000u32).map(|n| n.to_le_bytes()[0]).collect();
+    for hint in [0, 10, 1000, 100_000] {
+        let mut reader: &[u8] = &data;
+        let mut buf = UnownedReadBuffer::<16>::new();
+        let mut head = [0u8; 3];
+        buf.read_exact(&mut reader, &mut head).unwrap();
+        let mut rest = b"prefix".to_vec();
+        assert_eq!(
+            buf.read_to_end_with_hint(&mut reader, hint, &mut rest)
+                .unwrap(),
+            997
+        );
+        assert!(rest.capacity() >= hint);
+        assert_eq!(rest[..6], *b"prefix");
+        assert_eq!(rest[6..], data[3..]);
+    }
+}
+
+#[test]
+pub fn test_ring_read_buffer_wraps() {
+    use std::io::BufRead;
+
+    let data = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut reader: &[u8] = data;
+    let mut buf = UnownedRingReadBuffer::<16>::new();
+    assert_eq!(buf.fill_at_least(&mut reader, 10).unwrap(), 16);
+    let mut head = [0u8; 12];
+    buf.read_exact(&mut reader, &mut head).unwrap();
+    assert_eq!(&head, b"0123456789ab");
+    assert_eq!(buf.fill_at_least(&mut reader, 10).unwrap(), 16);
+    assert_eq!(
+        buf.internal_buffer_slices(),
+        (&b"cdef"[..], &b"ghijklmnopqr"[..])
+    );
+
+    let mut borrowed = buf.borrow(&mut reader);
+    assert_eq!(borrowed.fill_buf().unwrap(), b"cdef");
+    let mut line = Vec::new();
+    borrowed.read_until(b'k', &mut line).unwrap();
+    assert_eq!(line, b"cdefghijk");
+    let mut rest = String::new();
+    borrowed.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, "lmnopqrstuvwxyz");
+    assert!(buf.is_empty());
+    assert_eq!(
+        buf.fill_at_least(&mut reader, 17).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+pub fn test_ring_read_buffer_random() {
+    use std::io::BufRead;
+
+    let data: Vec<u8> = (0..RAND_SIZE * 4)
+        .map(|_| random::<u8>() % 32 + b'a')
+        .collect();
+    let mut reader = CountingReader(data.as_slice(), 0, 0);
+    let mut buf = UnownedRingReadBuffer::<64>::new();
+    let mut observed = Vec::new();
+    loop {
+        //Always keep some bytes buffered, which is the worst case for a compacting buffer.
+        let buffered = buf.fill_at_least(&mut reader, 40).unwrap();
+        if buffered == 0 {
+            break;
+        }
+        let amount = random::<usize>() % buffered.min(24) + 1;
+        match random::<u8>() % 4 {
+            0 => {
+                let mut chunk = vec![0u8; amount];
+                buf.read_exact(&mut reader, &mut chunk).unwrap();
+                observed.extend_from_slice(&chunk);
+            }
+            1 => {
+                let mut chunk = vec![0u8; amount];
+                let count = buf.read(&mut reader, &mut chunk).unwrap();
+                observed.extend_from_slice(&chunk[..count]);
+            }
+            2 => {
+                buf.read_until(&mut reader, b'a', &mut observed).unwrap();
+            }
+            _ => {
+                let mut borrowed = buf.borrow(&mut reader);
+                let available = borrowed.fill_buf().unwrap();
+                let count = amount.min(available.len());
+                observed.extend_from_slice(&available[..count]);
+                borrowed.consume(count);
+            }
+        }
+    }
+
+    assert_eq!(observed, data);
+}
+
+#[test]
+pub fn test_ring_read_buffer_wrapped_values() {
+    for offset in 0..16 {
+        let mut data = vec![b'-'; offset];
+        data.extend_from_slice(&0x0102_0304u32.to_be_bytes());
+        data.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+        data.extend_from_slice("€ä\n".as_bytes());
+        data.push(5);
+        data.extend_from_slice(&1.5f64.to_le_bytes());
+        data.extend_from_slice("aé€".as_bytes());
+        let mut reader = data.as_slice();
+        let mut buf = UnownedRingReadBuffer::<16>::new();
+        assert_eq!(buf.fill_at_least(&mut reader, 16).unwrap(), 16);
+        let mut prefix = vec![0u8; offset];
+        buf.read_exact(&mut reader, &mut prefix).unwrap();
+
+        //Every value starts at a different position, so each of them crosses the end of the internal buffer once.
+        assert_eq!(buf.read_u32_be(&mut reader).unwrap(), 0x0102_0304);
+        assert_eq!(buf.read_varint_u64(&mut reader).unwrap(), u64::MAX);
+        let mut line = String::new();
+        assert_eq!(buf.read_line(&mut reader, &mut line).unwrap(), 6);
+        assert_eq!(line, "€ä\n");
+        assert_eq!(buf.read_varint_i32(&mut reader).unwrap(), -3);
+        assert_eq!(buf.read_f64_le(&mut reader).unwrap(), 1.5);
+        let mut rest = String::new();
+        assert_eq!(buf.read_to_string(&mut reader, &mut rest).unwrap(), 6);
+        assert_eq!(rest, "aé€");
+        assert!(buf.is_empty());
+    }
+
+    //Incomplete values remain in the internal buffer.
+    let mut reader: &[u8] = &[1, 2, 0x80, 0x80];
+    let mut buf = UnownedRingReadBuffer::<16>::new();
+    assert_eq!(
+        buf.read_u64_le(&mut reader).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert_eq!(buf.available(), 4);
+    assert_eq!(buf.read_u16_le(&mut reader).unwrap(), 0x0201);
+    assert_eq!(
+        buf.read_varint_u32(&mut reader).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert_eq!(buf.available(), 2);
+
+    let mut reader: &[u8] = b"ab\xFFcd\n";
+    let mut buf = UnownedRingReadBuffer::<16>::new();
+    let mut line = String::new();
+    assert_eq!(
+        buf.read_line(&mut reader, &mut line).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    assert_eq!(buf.available(), 6);
+}
+
+#[test]
+pub fn test_read_line_limit() {
+    let mut reader = MaxChunkReader("short\nthis line is too long\nü€\n".as_bytes(), 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut line = String::new();
+    assert_eq!(buf.read_line_limit(&mut reader, 10, &mut line).unwrap(), 6);
+    assert_eq!(line, "short\n");
+    line.clear();
+    assert_eq!(buf.read_line_limit(&mut reader, 10, &mut line).unwrap(), 10);
+    assert_eq!(line, "this line ");
+    line.clear();
+    assert_eq!(
+        buf.read_line_limit(&mut reader, 100, &mut line).unwrap(),
+        12
+    );
+    assert_eq!(line, "is too long\n");
+    line.clear();
+    //The limit would split the €, it stays in the internal buffer.
+    assert_eq!(buf.read_line_limit(&mut reader, 3, &mut line).unwrap(), 2);
+    assert_eq!(line, "ü");
+    //0 always means EOF, a limit that cannot hold the next character is an error.
+    for limit in [0, 2] {
+        let err = buf
+            .read_line_limit(&mut reader, limit, &mut line)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(line, "ü");
+    }
+    assert_eq!(buf.read_line_limit(&mut reader, 4, &mut line).unwrap(), 4);
+    assert_eq!(line, "ü€\n");
+    assert_eq!(buf.read_line_limit(&mut reader, 0, &mut line).unwrap(), 0);
+    assert_eq!(buf.read_line_limit(&mut reader, 4, &mut line).unwrap(), 0);
+
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut cursor = Cursor::new("€x\n".as_bytes().to_vec());
+    let mut line = String::new();
+    assert_eq!(
+        buf.read_line_limit(&mut cursor, 2, &mut line)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(buf.available(), 5);
+}
+
+#[test]
+pub fn test_read_to_end_limit() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader: &[u8] = &data;
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut head = Vec::new();
+    assert_eq!(buf.read_to_end_limit(&mut reader, 0, &mut head).unwrap(), 0);
+    assert_eq!(
+        buf.read_to_end_limit(&mut reader, 37, &mut head).unwrap(),
+        37
+    );
+    assert_eq!(head, data[..37]);
+    let mut rest = Vec::new();
+    assert_eq!(
+        buf.read_to_end_limit(&mut reader, 1000, &mut rest).unwrap(),
+        63
+    );
+    assert_eq!(rest, data[37..]);
+    assert_eq!(
+        buf.read_to_end_limit(&mut reader, 1000, &mut rest).unwrap(),
+        0
+    );
+}
+
+#[test]
+pub fn test_fill_buf_more() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = ChunkReader(&data, &[6, 6, 100, 100], 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.fill_buf(&mut reader).unwrap(), &data[..6]);
+    buf.consume(4);
+    assert_eq!(buf.fill_buf_more(&mut reader).unwrap(), &data[4..12]);
+    assert_eq!(buf.fill_buf_more(&mut reader).unwrap(), &data[4..20]);
+    //The internal buffer is full, the reader is not called.
+    assert_eq!(buf.fill_buf_more(&mut reader).unwrap(), &data[4..20]);
+    buf.consume(16);
+    assert_eq!(buf.fill_buf_more(&mut reader).unwrap(), &data[20..36]);
+    buf.consume(10);
+    assert_eq!(buf.fill_buf_more(&mut reader).unwrap(), &data[30..36]);
+}
+
+#[test]
+pub fn test_read_exact_view() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.read_exact_view(&mut reader, 5).unwrap(), &data[..5]);
+    assert_eq!(buf.read_exact_view(&mut reader, 16).unwrap(), &data[5..21]);
+    assert_eq!(buf.read_exact_view(&mut reader, 0).unwrap(), b"");
+    assert_eq!(
+        buf.read_exact_view(&mut reader, 17).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(buf.read_exact_view(&mut reader, 10).unwrap(), &data[21..31]);
+    assert_eq!(
+        buf.read_exact_view(&mut reader, 10).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert_eq!(buf.internal_buffer(), &data[31..]);
+}
+
+#[test]
+pub fn test_fill_buf_mut() {
+    let data: Vec<u8> = (0..1000).map(|_| random::<u8>()).collect();
+    let expected: Vec<u8> = data.iter().map(|b| b ^ 0x5A).collect();
+
+    let mut reader = data.as_slice();
+    let mut buf = UnownedReadBuffer::<64>::new();
+    let mut decoded = Vec::new();
+    loop {
+        let available = buf.fill_buf_mut(&mut reader).unwrap();
+        if available.is_empty() {
+            break;
+        }
+
+        for b in available.iter_mut() {
+            *b ^= 0x5A;
+        }
+
+        //Half of the decoded bytes are read, the rest is taken via fill_buf and consume.
+        let mut chunk = vec![0u8; available.len() / 2];
+        buf.read_exact(&mut reader, &mut chunk).unwrap();
+        decoded.extend_from_slice(&chunk);
+        let rest = buf.fill_buf(&mut reader).unwrap();
+        decoded.extend_from_slice(rest);
+        let len = rest.len();
+        buf.consume(len);
+    }
+
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+pub fn test_write_buffer_predicates() {
+    let mut target = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    assert!(buf.is_empty());
+    assert!(!buf.is_full());
+    assert_eq!(buf.remaining_capacity(), 16);
+    buf.write_all(&mut target, b"0123456789").unwrap();
+    assert!(!buf.is_empty());
+    assert_eq!(buf.remaining_capacity(), 6);
+    assert_eq!(buf.try_write::<Vec<u8>>(b"abcdefgh"), 6);
+    assert!(buf.is_full());
+    buf.flush(&mut target).unwrap();
+    assert!(buf.is_empty());
+}
+
+#[test]
+pub fn test_fill_to_capacity() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = MaxChunkReader(&data, 7);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.fill_to_capacity(&mut reader).unwrap(), 16);
+    assert_eq!(buf.available(), 16);
+    assert_eq!(buf.internal_buffer(), &data[..16]);
+    //The internal buffer is full, the reader is not called.
+    assert_eq!(buf.fill_to_capacity(&mut reader).unwrap(), 0);
+
+    let mut chunk = [0u8; 10];
+    buf.read_exact(&mut reader, &mut chunk).unwrap();
+    assert_eq!(buf.fill_to_capacity(&mut reader).unwrap(), 10);
+    assert_eq!(buf.internal_buffer(), &data[10..26]);
+
+    buf.read_exact(&mut reader, &mut chunk).unwrap();
+    //Only 16 of 40 bytes are left in the stream.
+    assert_eq!(buf.fill_to_capacity(&mut reader).unwrap(), 10);
+    buf.read_exact(&mut reader, &mut chunk).unwrap();
+    assert_eq!(buf.fill_to_capacity(&mut reader).unwrap(), 4);
+    assert_eq!(buf.available(), 10);
+    assert_eq!(buf.internal_buffer(), &data[30..]);
+}
+
+#[test]
+pub fn test_fill_to_capacity_would_block() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = ChunkReader(&data, &[7, 3], 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        buf.fill_to_capacity(&mut reader).unwrap_err().kind(),
+        ErrorKind::WouldBlock
+    );
+    assert_eq!(buf.internal_buffer(), &data[..10]);
+    assert_eq!(buf.fill_to_capacity(&mut reader).unwrap(), 0);
+    assert_eq!(buf.available(), 10);
+}
+
+#[test]
+pub fn test_unconsume() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = data.as_slice();
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut line = Vec::new();
+    assert_eq!(buf.read_until(&mut reader, 5, &mut line).unwrap(), 6);
+    //Step back so the delimiter is read again.
+    buf.unconsume(1);
+    assert_eq!(buf.fill_buf(&mut reader).unwrap(), &data[5..16]);
+    buf.consume(11);
+    buf.unconsume(16);
+    assert_eq!(buf.internal_buffer(), &data[..16]);
+    buf.consume(16);
+
+    //Reading from the Read impl compacted the internal buffer, the read bytes are gone.
+    assert_eq!(buf.fill_buf(&mut reader).unwrap(), &data[16..32]);
+    buf.consume(3);
+    buf.unconsume(3);
+    assert_eq!(buf.internal_buffer(), &data[16..32]);
+}
+
+#[test]
+#[should_panic(expected = "amt <= self.read_count")]
+pub fn test_unconsume_too_much() {
+    let mut reader: &[u8] = b"abc";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    buf.fill_buf(&mut reader).unwrap();
+    buf.consume(2);
+    buf.unconsume(3);
+}
+
+#[test]
+pub fn test_read_into_internal_buffer_limit() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = CountingReader(data.as_slice(), 0, 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        buf.read_into_internal_buffer_limit(&mut reader, 0).unwrap(),
+        0
+    );
+    assert_eq!(reader.1, 0);
+    assert_eq!(
+        buf.read_into_internal_buffer_limit(&mut reader, 5).unwrap(),
+        5
+    );
+    assert_eq!(
+        buf.read_into_internal_buffer_limit(&mut reader, 7).unwrap(),
+        7
+    );
+    assert_eq!(reader.2, 7);
+    assert_eq!(buf.internal_buffer(), &data[..12]);
+    assert_eq!(
+        buf.read_into_internal_buffer_limit(&mut reader, 100)
+            .unwrap(),
+        4
+    );
+    assert!(buf.is_full());
+    //The internal buffer is full, the reader is not called.
+    assert_eq!(
+        buf.read_into_internal_buffer_limit(&mut reader, 5).unwrap(),
+        0
+    );
+    assert_eq!(reader.1, 3);
+
+    buf.consume(10);
+    assert_eq!(
+        buf.read_into_internal_buffer_limit(&mut reader, 3).unwrap(),
+        3
+    );
+    assert_eq!(buf.internal_buffer(), &data[10..19]);
+    assert_eq!(reader.2, 7);
+}
+
+#[test]
+pub fn test_fill_buf_limited() {
+    let data: Vec<u8> = (0..20u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = CountingReader(data.as_slice(), 0, 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.fill_buf_limited(&mut reader, 0).unwrap(), b"");
+    assert_eq!(reader.1, 0);
+    assert_eq!(buf.fill_buf_limited(&mut reader, 6).unwrap(), &data[..6]);
+    //The internal buffer is not empty, the reader is not called.
+    assert_eq!(buf.fill_buf_limited(&mut reader, 6).unwrap(), &data[..6]);
+    assert_eq!(reader.1, 1);
+    buf.consume(6);
+    assert_eq!(buf.fill_buf_limited(&mut reader, 10).unwrap(), &data[6..16]);
+    buf.consume(10);
+    assert_eq!(buf.fill_buf_limited(&mut reader, 10).unwrap(), &data[16..]);
+    buf.consume(4);
+    assert_eq!(buf.fill_buf_limited(&mut reader, 10).unwrap(), b"");
+    assert_eq!(reader.2, 10);
+}
+
+#[test]
+pub fn test_try_read_into_internal_buffer() {
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = CountingReader(data.as_slice(), 0, 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut head = [0u8; 15];
+    buf.read_exact(&mut reader, &mut head).unwrap();
+    buf.copy_into_internal_buffer(&data[..14]);
+    //One byte left.
+    assert_eq!(buf.try_read_into_internal_buffer(&mut reader).unwrap(), 1);
+    assert!(buf.is_full());
+    //Exactly full, the reader is not called.
+    assert_eq!(buf.try_read_into_internal_buffer(&mut reader).unwrap(), 0);
+    assert_eq!(reader.1, 2);
+
+    buf.consume(16);
+    assert_eq!(buf.try_read_into_internal_buffer(&mut reader).unwrap(), 16);
+    assert_eq!(buf.try_read_into_internal_buffer(&mut reader).unwrap(), 0);
+    assert_eq!(reader.1, 3);
+}
+
+#[test]
+pub fn test_expect_bytes() {
+    let mut data = b"\x7fELF".to_vec();
+    data.extend((0..40u32).map(|n| n.to_le_bytes()[0]));
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert!(buf.expect_bytes(&mut reader, b"\x7fELF").unwrap());
+    assert!(buf.expect_bytes(&mut reader, b"").unwrap());
+    assert!(!buf.expect_bytes(&mut reader, &[0, 1, 9]).unwrap());
+    //The mismatched bytes were consumed.
+    assert_eq!(buf.read_array::<1, _>(&mut reader).unwrap(), [3]);
+    //Longer than the internal buffer.
+    assert!(buf.expect_bytes(&mut reader, &data[8..28]).unwrap());
+    let mut wrong = data[28..].to_vec();
+    wrong[5] = 0xFF;
+    assert!(!buf.expect_bytes(&mut reader, &wrong).unwrap());
+    assert_eq!(
+        buf.expect_bytes(&mut reader, b"x").unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+pub fn test_underlying_read_calls() {
+    let data: Vec<u8> = (0..200u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = CountingReader(data.as_slice(), 0, 0);
+    let mut buf = UnownedReadBuffer::<64>::new();
+    let mut byte = [0u8; 1];
+    for expected in &data[..128] {
+        buf.read_exact(&mut reader, &mut byte).unwrap();
+        assert_eq!(byte[0], *expected);
+    }
+
+    assert_eq!(buf.underlying_read_calls(), 2);
+    //LARGE READ bypassing the internal buffer.
+    let mut large = [0u8; 64];
+    buf.read_exact(&mut reader, &mut large).unwrap();
+    assert_eq!(buf.underlying_read_calls(), 3);
+    assert_eq!(buf.underlying_read_calls(), reader.1 as u64);
+}
+
+#[test]
+pub fn test_underlying_write_calls() {
+    let mut target = CountingWriter(Vec::new(), 0, usize::MAX);
+    let mut buf = UnownedWriteBuffer::<64>::new();
+    for n in 0..128u8 {
+        buf.write_all(&mut target, &[n]).unwrap();
+    }
+
+    assert_eq!(buf.underlying_write_calls(), 1);
+    buf.flush(&mut target).unwrap();
+    assert_eq!(buf.underlying_write_calls(), 2);
+    //LARGE WRITE bypassing the internal buffer.
+    buf.write_all(&mut target, &[0u8; 64]).unwrap();
+    assert_eq!(buf.underlying_write_calls(), 3);
+    assert_eq!(buf.underlying_write_calls(), target.1 as u64);
+    assert_eq!(target.0.len(), 192);
+}
+
+#[test]
+pub fn test_try_consume() {
+    let mut reader: &[u8] = b"0123456789";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.try_consume(0), Ok(()));
+    assert_eq!(buf.try_consume(1), Err(0));
+    buf.fill_buf(&mut reader).unwrap();
+    assert_eq!(buf.try_consume(0), Ok(()));
+    assert_eq!(buf.try_consume(11), Err(10));
+    assert_eq!(buf.internal_buffer(), b"0123456789");
+    assert_eq!(buf.try_consume(3), Ok(()));
+    assert_eq!(buf.try_consume(8), Err(7));
+    assert_eq!(buf.try_consume(7), Ok(()));
+    assert!(buf.internal_buffer().is_empty());
+}
+
+#[test]
+pub fn test_consume_up_to() {
+    let mut reader: &[u8] = b"0123456789";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.consume_up_to(5), 0);
+    buf.fill_buf(&mut reader).unwrap();
+    assert_eq!(buf.consume_up_to(0), 0);
+    assert_eq!(buf.consume_up_to(3), 3);
+    assert_eq!(buf.consume_up_to(7), 7);
+    assert_eq!(buf.consume_up_to(1), 0);
+
+    let mut reader: &[u8] = b"0123456789";
+    buf.fill_buf(&mut reader).unwrap();
+    assert_eq!(buf.consume_up_to(11), 10);
+}
+
+#[test]
+pub fn test_copy_into_internal_buffer_compacts() {
+    let mut reader: &[u8] = b"0123456789abcdef";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut head = [0u8; 10];
+    buf.read_exact(&mut reader, &mut head).unwrap();
+    assert_eq!(buf.available_space(), 0);
+    //Only fits after the 10 already read bytes were compacted away.
+    buf.copy_into_internal_buffer(b"0123456789");
+    assert_eq!(buf.internal_buffer(), b"abcdef0123456789");
+}
+
+#[test]
+#[should_panic(
+    expected = "3 bytes do not fit into the internal buffer, 0 bytes are free at its end and 2 bytes after compacting it"
+)]
+pub fn test_copy_into_internal_buffer_never_fits() {
+    let mut reader: &[u8] = b"0123456789abcdef";
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut head = [0u8; 2];
+    buf.read_exact(&mut reader, &mut head).unwrap();
+    buf.copy_into_internal_buffer(b"xyz");
+}
+
+#[test]
+pub fn test_read_exact_or_restore() {
+    let data: Vec<u8> = (0..48u32).map(|n| n.to_le_bytes()[0]).collect();
+    //The request needs three reads of 6 bytes.
+    for offset in 0..16 {
+        let mut reader = MaxChunkReader(&data[..offset], 6);
+        let mut buf = UnownedReadBuffer::<16>::new();
+        let mut target = [0u8; 16];
+        let (count, err) = buf
+            .read_exact_or_restore(&mut reader, &mut target)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(count, 0);
+        assert_eq!(buf.internal_buffer(), &data[..offset]);
+
+        //Retry after the rest of the data arrived.
+        let mut reader = MaxChunkReader(&data[offset..], 6);
+        buf.read_exact_or_restore(&mut reader, &mut target).unwrap();
+        assert_eq!(target, data[..16]);
+    }
+
+    //Larger than the internal buffer, the error reports the progress.
+    for offset in 0..48 {
+        let mut reader = MaxChunkReader(&data[..offset], 6);
+        let mut buf = UnownedReadBuffer::<16>::new();
+        let mut target = [0u8; 48];
+        let (count, err) = buf
+            .read_exact_or_restore(&mut reader, &mut target)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(count, offset);
+        assert_eq!(target[..count], data[..offset]);
+    }
+}
+
+#[test]
+pub fn test_read_to_end_counted() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    for n in 0..100 {
+        let mut reader = BlockAtReader(&data, n, false);
+        let mut buf = UnownedReadBuffer::<16>::new();
+        let mut target = Vec::new();
+        let (count, err) = buf
+            .read_to_end_counted(&mut reader, &mut target)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(count, n);
+        assert_eq!(target, data[..n]);
+        assert_eq!(buf.available(), 0);
+
+        assert_eq!(
+            buf.read_to_end_counted(&mut reader, &mut target).unwrap(),
+            100 - n
+        );
+        assert_eq!(target, data);
+    }
+}
+
+#[test]
+pub fn test_read_until_counted() {
+    let mut data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0] % 50 + 1).collect();
+    data[80] = 0;
+    for n in 0..80 {
+        let mut reader = BlockAtReader(&data, n, false);
+        let mut buf = UnownedReadBuffer::<16>::new();
+        let mut target = Vec::new();
+        let (count, err) = buf
+            .read_until_counted(&mut reader, 0, &mut target)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(count, n);
+        assert_eq!(target, data[..n]);
+        assert_eq!(buf.available(), 0);
+
+        assert_eq!(
+            buf.read_until_counted(&mut reader, 0, &mut target).unwrap(),
+            81 - n
+        );
+        assert_eq!(target, data[..81]);
+    }
+}
+
+#[test]
+pub fn test_read_to_string_counted() {
+    let text = "aäb€c😀d".repeat(10);
+    let data = text.as_bytes();
+    for n in 0..data.len() {
+        let mut reader = BlockAtReader(data, n, false);
+        let mut buf = UnownedReadBuffer::<16>::new();
+        let mut target = String::new();
+        let (count, err) = buf
+            .read_to_string_counted(&mut reader, &mut target)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(count, target.len());
+        //Only an incomplete multibyte sequence remains in the internal buffer.
+        assert!(buf.available() <= 3);
+        assert_eq!(count + buf.available(), n);
+
+        let rest = buf
+            .read_to_string_counted(&mut reader, &mut target)
+            .unwrap();
+        assert_eq!(count + rest, data.len());
+        assert_eq!(target, text);
+    }
+}
+
+/// Reads a record using only the extension trait.
+fn read_record<R: UnownedReadExt, T: Read>(
+    buf: &mut R,
+    read: &mut T,
+) -> std::io::Result<([u8; 2], u64, i64, Vec<u8>)> {
+    let magic = buf.read_array::<2, T>(read)?;
+    let len = buf.read_varint_u64(read)?;
+    let delta = buf.read_varint_i64(read)?;
+    let mut name = Vec::new();
+    buf.read_cstr(read, &mut name)?;
+    Ok((magic, len, delta, name))
+}
+
+#[test]
+pub fn test_read_ext() {
+    let mut data = b"PK".to_vec();
+    data.extend_from_slice(&[0xAC, 0x02, 0x03]);
+    data.extend_from_slice(b"name\0 -42 17");
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let (magic, len, delta, name) = read_record(&mut buf, &mut reader).unwrap();
+    assert_eq!(&magic, b"PK");
+    assert_eq!(len, 300);
+    assert_eq!(delta, -2);
+    assert_eq!(name, b"name");
+    assert_eq!(
+        UnownedReadExt::read_ascii_i64(&mut buf, &mut reader).unwrap(),
+        -42
+    );
+    assert_eq!(
+        UnownedReadExt::read_ascii_u64(&mut buf, &mut reader).unwrap(),
+        17
+    );
+}
+
+/// Reads a header using only the fixed size helpers of the extension trait.
+fn read_header<R: UnownedReadExt, T: Read>(
+    buf: &mut R,
+    read: &mut T,
+) -> std::io::Result<(u16, u32, i64, f64, Vec<u8>)> {
+    let version = buf.read_u16_be(read)?;
+    let flags = buf.read_int::<u32, LittleEndian>(read)?;
+    let offset = buf.read_i64_le(read)?;
+    let scale = buf.read_f64_be(read)?;
+    let mut name = Vec::new();
+    buf.read_nul_terminated(read, 16, &mut name)?;
+    Ok((version, flags, offset, scale, name))
+}
+
+#[test]
+pub fn test_read_ext_fixed_size() {
+    let mut data = 7u16.to_be_bytes().to_vec();
+    data.extend_from_slice(&0xDEAD_BEEFu32.to_le_bytes());
+    data.extend_from_slice(&(-5i64).to_le_bytes());
+    data.extend_from_slice(&0.25f64.to_be_bytes());
+    data.extend_from_slice(b"header\0");
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let (version, flags, offset, scale, name) = read_header(&mut buf, &mut reader).unwrap();
+    assert_eq!(version, 7);
+    assert_eq!(flags, 0xDEAD_BEEF);
+    assert_eq!(offset, -5);
+    assert_eq!(scale, 0.25);
+    assert_eq!(name, b"header");
+    assert_eq!(
+        UnownedReadExt::read_u8(&mut buf, &mut reader)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+pub fn test_owned_read_buffer_fill_buf_contract() {
+    use std::io::BufRead;
+
+    let data: Vec<u8> = (0..40u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut buf = OwnedReadBuffer::<_, 16>::new(CountingReader(data.as_slice(), 0, 0));
+    let first = buf.fill_buf().unwrap().to_vec();
+    assert_eq!(first, data[..16]);
+    //Without consume the same bytes are returned and the Read impl is not called again.
+    assert_eq!(buf.fill_buf().unwrap(), first.as_slice());
+    assert_eq!(buf.fill_buf().unwrap(), first.as_slice());
+    assert_eq!(buf.get_ref().1, 1);
+
+    buf.consume(6);
+    assert_eq!(buf.fill_buf().unwrap(), &data[6..16]);
+    assert_eq!(buf.get_ref().1, 1);
+    buf.consume(10);
+    assert_eq!(buf.fill_buf().unwrap(), &data[16..32]);
+    assert_eq!(buf.get_ref().1, 2);
+    buf.consume(0);
+    assert_eq!(buf.fill_buf().unwrap(), &data[16..32]);
+    buf.consume(16);
+    assert_eq!(buf.fill_buf().unwrap(), &data[32..]);
+    buf.consume(8);
+    //EOF is an empty slice.
+    assert_eq!(buf.fill_buf().unwrap(), b"");
+    assert_eq!(buf.fill_buf().unwrap(), b"");
+}
+
+#[test]
+pub fn test_interrupted_is_retried() {
+    let text = "first line\nsecond line that is longer than the buffer\nthird äöü line\n".repeat(4);
+    let data = text.as_bytes();
+
+    let mut reader = AlternatingErrorReader(data, 7, ErrorKind::Interrupted, true);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = vec![0u8; data.len()];
+    buf.read_exact(&mut reader, &mut target).unwrap();
+    assert_eq!(target, data);
+
+    let mut reader = AlternatingErrorReader(data, 100, ErrorKind::Interrupted, true);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = vec![0u8; data.len()];
+    buf.read_exact(&mut reader, &mut target).unwrap();
+    assert_eq!(target, data);
+
+    let mut reader = AlternatingErrorReader(data, 100, ErrorKind::Interrupted, true);
+    let mut buf = UnownedRingReadBuffer::<16>::new();
+    let mut target = vec![0u8; data.len()];
+    buf.read_exact(&mut reader, &mut target).unwrap();
+    assert_eq!(target, data);
+
+    let mut reader = AlternatingErrorReader(data, 7, ErrorKind::Interrupted, true);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        if buf.read_line(&mut reader, &mut line).unwrap() == 0 {
+            break;
+        }
+        lines.push(line);
+    }
+    assert_eq!(lines.concat(), text);
+    assert_eq!(lines.len(), 12);
+
+    let mut reader = AlternatingErrorReader(data, 7, ErrorKind::Interrupted, true);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = Vec::new();
+    buf.read_until(&mut reader, b'\n', &mut target).unwrap();
+    assert_eq!(target, b"first line\n");
+    buf.read_to_end(&mut reader, &mut target).unwrap();
+    assert_eq!(target, data);
+
+    let mut reader = AlternatingErrorReader(data, 7, ErrorKind::Interrupted, true);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = String::new();
+    buf.read_to_string(&mut reader, &mut target).unwrap();
+    assert_eq!(target, text);
+
+    let mut reader = AlternatingErrorReader(data, 7, ErrorKind::Interrupted, true);
+    let mut buf = UnownedRingReadBuffer::<16>::new();
+    let mut target = Vec::new();
+    buf.read_until(&mut reader, 0, &mut target).unwrap();
+    assert_eq!(target, data);
+}
+
+#[test]
+pub fn test_read_vectored() {
+    use std::io::IoSliceMut;
+
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = Cursor::new(data.clone());
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let (mut a, mut b, mut c) = ([0u8; 2], [0u8; 3], [0u8; 4]);
+
+    //Internal buffer empty and the buffers are small, the internal buffer is filled once.
+    let count = buf
+        .read_vectored(
+            &mut reader,
+            &mut [
+                IoSliceMut::new(&mut a),
+                IoSliceMut::new(&mut b),
+                IoSliceMut::new(&mut c),
+            ],
+        )
+        .unwrap();
+    assert_eq!(count, 9);
+    assert_eq!([&a[..], &b[..], &c[..]].concat(), data[..9]);
+    assert_eq!(buf.available(), 7);
+
+    //Only the buffered bytes are returned, the second buffer is filled partially.
+    let (mut a, mut b, mut c) = ([0u8; 5], [0u8; 5], [0u8; 5]);
+    let count = buf
+        .read_vectored(
+            &mut reader,
+            &mut [
+                IoSliceMut::new(&mut a),
+                IoSliceMut::new(&mut b),
+                IoSliceMut::new(&mut c),
+            ],
+        )
+        .unwrap();
+    assert_eq!(count, 7);
+    assert_eq!(a, data[9..14]);
+    assert_eq!(b[..2], data[14..16]);
+    assert_eq!(c, [0u8; 5]);
+    assert_eq!(reader.position(), 16);
+
+    //LARGE READ directly into the buffers.
+    let (mut a, mut b, mut c) = ([0u8; 6], [0u8; 6], [0u8; 6]);
+    let count = buf
+        .read_vectored(
+            &mut reader,
+            &mut [
+                IoSliceMut::new(&mut a),
+                IoSliceMut::new(&mut b),
+                IoSliceMut::new(&mut c),
+            ],
+        )
+        .unwrap();
+    assert_eq!(count, 18);
+    assert_eq!([&a[..], &b[..], &c[..]].concat(), data[16..34]);
+    assert_eq!(buf.available(), 0);
+
+    //Through the Read impl of the borrowed buffer, the empty buffer in between is skipped.
+    let mut borrowed = buf.borrow(&mut reader);
+    let (mut a, mut c) = ([0u8; 4], [0u8; 4]);
+    let count = borrowed
+        .read_vectored(&mut [
+            IoSliceMut::new(&mut a),
+            IoSliceMut::new(&mut []),
+            IoSliceMut::new(&mut c),
+        ])
+        .unwrap();
+    assert_eq!(count, 8);
+    assert_eq!([&a[..], &c[..]].concat(), data[34..42]);
+
+    let mut rest = Vec::new();
+    borrowed.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, data[42..]);
+    assert_eq!(
+        buf.read_vectored(&mut reader, &mut [IoSliceMut::new(&mut a)])
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+pub fn test_read_ints() {
+    let mut data = Vec::new();
+    data.push(0xFE);
+    data.push(0x80);
+    data.extend_from_slice(&0x1234u16.to_be_bytes());
+    data.extend_from_slice(&(-2i16).to_le_bytes());
+    data.extend_from_slice(&0x1234_5678u32.to_le_bytes());
+    data.extend_from_slice(&i32::MIN.to_be_bytes());
+    data.extend_from_slice(&0x0102_0304_0506_0708u64.to_be_bytes());
+    data.extend_from_slice(&(-3i64).to_le_bytes());
+    data.extend_from_slice(&u128::MAX.to_le_bytes());
+    data.extend_from_slice(&(i128::MIN + 5).to_be_bytes());
+
+    //Reading single bytes splits every value across several reads.
+    let mut reader = MaxChunkReader(&data, 1);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(buf.read_u8(&mut reader).unwrap(), 0xFE);
+    assert_eq!(buf.read_i8(&mut reader).unwrap(), i8::MIN);
+    assert_eq!(buf.read_u16_be(&mut reader).unwrap(), 0x1234);
+    assert_eq!(buf.read_i16_le(&mut reader).unwrap(), -2);
+    assert_eq!(buf.read_u32_le(&mut reader).unwrap(), 0x1234_5678);
+    assert_eq!(buf.read_i32_be(&mut reader).unwrap(), i32::MIN);
+    assert_eq!(buf.read_u64_be(&mut reader).unwrap(), 0x0102_0304_0506_0708);
+    assert_eq!(buf.read_i64_le(&mut reader).unwrap(), -3);
+    assert_eq!(buf.read_u128_le(&mut reader).unwrap(), u128::MAX);
+    assert_eq!(buf.read_i128_be(&mut reader).unwrap(), i128::MIN + 5);
+    assert_eq!(
+        buf.read_u8(&mut reader).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+
+    //The same values through the borrowed buffer, with a reader that splits at other offsets.
+    let mut reader = ChunkReader(&data, &[3, 7, 5, 16, 9, 16, 16, 16], 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut borrowed = buf.borrow(&mut reader);
+    assert_eq!(borrowed.read_u8().unwrap(), 0xFE);
+    assert_eq!(borrowed.read_i8().unwrap(), i8::MIN);
+    assert_eq!(borrowed.read_u16_be().unwrap(), 0x1234);
+    assert_eq!(borrowed.read_i16_le().unwrap(), -2);
+    assert_eq!(borrowed.read_u32_le().unwrap(), 0x1234_5678);
+    assert_eq!(borrowed.read_i32_be().unwrap(), i32::MIN);
+    assert_eq!(borrowed.read_u64_be().unwrap(), 0x0102_0304_0506_0708);
+    assert_eq!(borrowed.read_i64_le().unwrap(), -3);
+    assert_eq!(borrowed.read_u128_le().unwrap(), u128::MAX);
+    assert_eq!(borrowed.read_i128_be().unwrap(), i128::MIN + 5);
+}
+
+#[test]
+pub fn test_read_ints_eof() {
+    let data = [0xAAu8; 16];
+    let check =
+        |len: usize, f: &dyn Fn(&mut UnownedReadBuffer<16>, &mut &[u8]) -> std::io::Result<()>| {
+            let mut reader = &data[..len - 1];
+            let mut buf = UnownedReadBuffer::<16>::new();
+            assert_eq!(
+                f(&mut buf, &mut reader).unwrap_err().kind(),
+                ErrorKind::UnexpectedEof
+            );
+            //Nothing was consumed.
+            assert_eq!(buf.available(), len - 1);
+        };
+
+    check(1, &|buf, reader| buf.read_u8(reader).map(drop));
+    check(1, &|buf, reader| buf.read_i8(reader).map(drop));
+    check(2, &|buf, reader| buf.read_u16_be(reader).map(drop));
+    check(2, &|buf, reader| buf.read_u16_le(reader).map(drop));
+    check(2, &|buf, reader| buf.read_i16_be(reader).map(drop));
+    check(2, &|buf, reader| buf.read_i16_le(reader).map(drop));
+    check(4, &|buf, reader| buf.read_u32_be(reader).map(drop));
+    check(4, &|buf, reader| buf.read_u32_le(reader).map(drop));
+    check(4, &|buf, reader| buf.read_i32_be(reader).map(drop));
+    check(4, &|buf, reader| buf.read_i32_le(reader).map(drop));
+    check(8, &|buf, reader| buf.read_u64_be(reader).map(drop));
+    check(8, &|buf, reader| buf.read_u64_le(reader).map(drop));
+    check(8, &|buf, reader| buf.read_i64_be(reader).map(drop));
+    check(8, &|buf, reader| buf.read_i64_le(reader).map(drop));
+    check(16, &|buf, reader| buf.read_u128_be(reader).map(drop));
+    check(16, &|buf, reader| buf.read_u128_le(reader).map(drop));
+    check(16, &|buf, reader| buf.read_i128_be(reader).map(drop));
+    check(16, &|buf, reader| buf.read_i128_le(reader).map(drop));
+}
+
+#[test]
+pub fn test_ensure_readable_at_least() {
+    let data: Vec<u8> = (0..20u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut reader = CountingReader(MaxChunkReader(&data, 1), 0, 0);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert!(buf.ensure_readable_at_least(&mut reader, 0).unwrap());
+    assert_eq!(reader.1, 0);
+    assert!(buf.ensure_readable_at_least(&mut reader, 12).unwrap());
+    assert_eq!(buf.available(), 12);
+    assert_eq!(reader.1, 12);
+    //Already buffered, the reader is not called.
+    assert!(buf.ensure_readable_at_least(&mut reader, 12).unwrap());
+    assert_eq!(reader.1, 12);
+    assert_eq!(
+        buf.ensure_readable_at_least(&mut reader, 17)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+
+    buf.consume(10);
+    assert!(buf.ensure_readable_at_least(&mut reader, 10).unwrap());
+    assert!(!buf.ensure_readable_at_least(&mut reader, 16).unwrap());
+    assert_eq!(buf.internal_buffer(), &data[10..]);
+}
+
+#[test]
+pub fn test_float_round_trip() {
+    let nan32 = f32::from_bits(0x7FC0_1234);
+    let nan64 = f64::from_bits(0xFFF8_0000_DEAD_BEEF);
+    let values32 = [
+        nan32,
+        0.0,
+        -0.0,
+        f32::from_bits(1),
+        f32::MIN_POSITIVE / 2.0,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        1.5,
+    ];
+    let values64 = [
+        nan64,
+        0.0,
+        -0.0,
+        f64::from_bits(1),
+        f64::MIN_POSITIVE / 2.0,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        -2.25,
+    ];
+
+    let mut target = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    for (v32, v64) in values32.iter().zip(values64.iter()) {
+        buf.write_f32_be(&mut target, *v32).unwrap();
+        buf.write_f32_le(&mut target, *v32).unwrap();
+        buf.write_f64_be(&mut target, *v64).unwrap();
+        buf.write_f64_le(&mut target, *v64).unwrap();
+    }
+    buf.flush(&mut target).unwrap();
+    assert_eq!(target.len(), values32.len() * 24);
+
+    let mut reader = MaxChunkReader(&target, 5);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    for (v32, v64) in values32.iter().zip(values64.iter()) {
+        assert_eq!(
+            buf.read_f32_be(&mut reader).unwrap().to_bits(),
+            v32.to_bits()
+        );
+        assert_eq!(
+            buf.read_f32_le(&mut reader).unwrap().to_bits(),
+            v32.to_bits()
+        );
+        assert_eq!(
+            buf.read_f64_be(&mut reader).unwrap().to_bits(),
+            v64.to_bits()
+        );
+        assert_eq!(
+            buf.read_f64_le(&mut reader).unwrap().to_bits(),
+            v64.to_bits()
+        );
+    }
+    assert_eq!(
+        buf.read_f32_le(&mut reader).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+pub fn test_pump_from() {
+    let data: Vec<u8> = (0..1000u32).map(|n| n.to_le_bytes()[0]).collect();
+    let mut src = AlternatingErrorReader(&data, 7, ErrorKind::Interrupted, true);
+    let mut target = CountingWriter(Vec::new(), 0, usize::MAX);
+    let mut buf = UnownedWriteBuffer::<64>::new();
+    assert_eq!(buf.pump_from(&mut target, &mut src).unwrap(), 1000);
+    //The last partial buffer is not written.
+    assert_eq!(target.0, data[..960]);
+    assert_eq!(target.1, 15);
+    assert_eq!(buf.available(), 24);
+    buf.flush(&mut target).unwrap();
+    assert_eq!(target.0, data);
+
+    let mut src = FailingReader;
+    let err = buf.pump_from(&mut target, &mut src).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+}
+
+/// Writes the values with the given byte order and checks the encoding against expected.
+fn write_ints<I: EndianInt + std::fmt::Debug + PartialEq, O: ByteOrder>(
+    values: &[I],
+    encode: fn(I) -> Vec<u8>,
+    target: &mut Vec<u8>,
+    buf: &mut UnownedWriteBuffer<16>,
+) {
+    for value in values {
+        let start = target.len() + buf.size() - buf.available();
+        buf.write_int::<I, O>(target, *value).unwrap();
+        buf.flush(target).unwrap();
+        assert_eq!(target[start..], encode(*value));
+    }
+}
+
+/// Reads the values with the given byte order.
+fn read_ints<I: EndianInt + std::fmt::Debug + PartialEq, O: ByteOrder>(
+    values: &[I],
+    reader: &mut MaxChunkReader<'_>,
+    buf: &mut UnownedReadBuffer<16>,
+) {
+    for value in values {
+        assert_eq!(buf.read_int::<I, O>(reader).unwrap(), *value);
+    }
+}
+
+#[test]
+pub fn test_read_write_int() {
+    let u16s = [0, 1, 0x1234, u16::MAX];
+    let i16s = [0, -1, i16::MIN, i16::MAX];
+    let u32s = [0, 1, 0x1234_5678, u32::MAX];
+    let i32s = [0, -1, i32::MIN, i32::MAX];
+    let u64s = [0, 1, 0x0102_0304_0506_0708, u64::MAX];
+    let i64s = [0, -1, i64::MIN, i64::MAX];
+    let u128s = [0, 1, 0x0102_0304_0506_0708_090A_0B0C_0D0E_0F10, u128::MAX];
+    let i128s = [0, -1, i128::MIN, i128::MAX];
+
+    let mut target = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    write_ints::<u8, BigEndian>(
+        &[0, 7, u8::MAX],
+        |v| v.to_be_bytes().to_vec(),
+        &mut target,
+        &mut buf,
+    );
+    write_ints::<i8, LittleEndian>(
+        &[0, -7, i8::MIN],
+        |v| v.to_le_bytes().to_vec(),
+        &mut target,
+        &mut buf,
+    );
+    write_ints::<u16, BigEndian>(&u16s, |v| v.to_be_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<u16, LittleEndian>(&u16s, |v| v.to_le_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<i16, BigEndian>(&i16s, |v| v.to_be_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<i16, LittleEndian>(&i16s, |v| v.to_le_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<u32, BigEndian>(&u32s, |v| v.to_be_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<u32, LittleEndian>(&u32s, |v| v.to_le_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<i32, BigEndian>(&i32s, |v| v.to_be_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<i32, LittleEndian>(&i32s, |v| v.to_le_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<u64, BigEndian>(&u64s, |v| v.to_be_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<u64, LittleEndian>(&u64s, |v| v.to_le_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<i64, BigEndian>(&i64s, |v| v.to_be_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<i64, LittleEndian>(&i64s, |v| v.to_le_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<u128, BigEndian>(&u128s, |v| v.to_be_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<u128, LittleEndian>(&u128s, |v| v.to_le_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<i128, BigEndian>(&i128s, |v| v.to_be_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<i128, LittleEndian>(&i128s, |v| v.to_le_bytes().to_vec(), &mut target, &mut buf);
+    write_ints::<u32, NativeEndian>(&u32s, |v| v.to_ne_bytes().to_vec(), &mut target, &mut buf);
+
+    //Chunks of 5 bytes make most values straddle a refill.
+    let mut reader = MaxChunkReader(&target, 5);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    read_ints::<u8, BigEndian>(&[0, 7, u8::MAX], &mut reader, &mut buf);
+    read_ints::<i8, LittleEndian>(&[0, -7, i8::MIN], &mut reader, &mut buf);
+    read_ints::<u16, BigEndian>(&u16s, &mut reader, &mut buf);
+    read_ints::<u16, LittleEndian>(&u16s, &mut reader, &mut buf);
+    read_ints::<i16, BigEndian>(&i16s, &mut reader, &mut buf);
+    read_ints::<i16, LittleEndian>(&i16s, &mut reader, &mut buf);
+    read_ints::<u32, BigEndian>(&u32s, &mut reader, &mut buf);
+    read_ints::<u32, LittleEndian>(&u32s, &mut reader, &mut buf);
+    read_ints::<i32, BigEndian>(&i32s, &mut reader, &mut buf);
+    read_ints::<i32, LittleEndian>(&i32s, &mut reader, &mut buf);
+    read_ints::<u64, BigEndian>(&u64s, &mut reader, &mut buf);
+    read_ints::<u64, LittleEndian>(&u64s, &mut reader, &mut buf);
+    read_ints::<i64, BigEndian>(&i64s, &mut reader, &mut buf);
+    read_ints::<i64, LittleEndian>(&i64s, &mut reader, &mut buf);
+    read_ints::<u128, BigEndian>(&u128s, &mut reader, &mut buf);
+    read_ints::<u128, LittleEndian>(&u128s, &mut reader, &mut buf);
+    read_ints::<i128, BigEndian>(&i128s, &mut reader, &mut buf);
+    read_ints::<i128, LittleEndian>(&i128s, &mut reader, &mut buf);
+    read_ints::<u32, NativeEndian>(&u32s, &mut reader, &mut buf);
+
+    //Nothing is consumed at EOF.
+    let mut reader = MaxChunkReader(&[1, 2, 3], 5);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        buf.read_int::<u32, BigEndian>(&mut reader)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert_eq!(buf.internal_buffer(), &[1, 2, 3]);
+}
+
+#[test]
+pub fn test_push_all() {
+    let data: Vec<u8> = (0..100u32).map(|n| n.to_le_bytes()[0]).collect();
+    let chunks: [&[u8]; 5] = [
+        &data[..3],
+        &data[3..3],
+        &data[3..20],
+        &data[20..90],
+        &data[90..],
+    ];
+    let mut target = CountingWriter(Vec::new(), 0, usize::MAX);
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    buf.push_all(&mut target, &chunks).unwrap();
+    assert_eq!(target.0.len() + buf.size() - buf.available(), 100);
+    buf.flush(&mut target).unwrap();
+    assert_eq!(target.0, data);
+
+    let mut expected = CountingWriter(Vec::new(), 0, usize::MAX);
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    for chunk in chunks {
+        buf.write_all(&mut expected, chunk).unwrap();
+    }
+    buf.flush(&mut expected).unwrap();
+    assert_eq!(expected.1, target.1);
+}
+
+#[test]
+pub fn test_write_buffer_truncate() {
+    let mut sink = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    buf.write_all(&mut sink, b"head").unwrap();
+    let mark = buf.flushable();
+    assert_eq!(mark, 4);
+    buf.write_all(&mut sink, b"speculative").unwrap();
+    assert_eq!(buf.flushable(), 15);
+
+    buf.truncate(mark);
+    assert_eq!(buf.flushable(), 4);
+    //Truncating to a larger length does nothing.
+    buf.truncate(10);
+    assert_eq!(buf.flushable(), 4);
+
+    buf.write_all(&mut sink, b"tail").unwrap();
+    buf.flush(&mut sink).unwrap();
+    assert_eq!(sink, b"headtail");
+
+    buf.truncate(0);
+    assert!(buf.is_empty());
+}
+
+#[test]
+pub fn test_borrowed_write_buffer_extend() {
+    let data = (0..100u8).collect::<Vec<u8>>();
+    let mut sink = Vec::new();
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    {
+        let mut borrowed = buf.borrow(&mut sink);
+        borrowed.extend(data.iter().copied().take(50));
+        borrowed.extend(&data[50..]);
+        borrowed.flush().unwrap();
+    }
+    assert_eq!(sink, data);
+    //Only full internal buffers are written.
+    assert_eq!(buf.underlying_write_calls(), 7);
+
+    let mut failing = FailingWriter(Vec::new(), 20);
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    let mut iter = data.iter().copied();
+    let err = buf
+        .borrow(&mut failing)
+        .try_extend(iter.by_ref())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    assert_eq!(failing.0, &data[..20]);
+    //No byte taken from the iterator is lost.
+    assert_eq!(buf.flushable(), 12);
+    assert_eq!(iter.next(), Some(data[32]));
+}
+
+#[test]
+pub fn test_drain_buffered() {
+    let data = b"HELLO\r\nrest of the stream";
+    let mut reader = MaxChunkReader(data, 16);
+    let mut buf = UnownedReadBuffer::<32>::new();
+    let mut line = String::new();
+    buf.read_line(&mut reader, &mut line).unwrap();
+    assert_eq!(line, "HELLO\r\n");
+
+    let mut out = b"prefix ".to_vec();
+    assert_eq!(buf.drain_buffered(&mut out), 9);
+    assert_eq!(out, b"prefix rest of t");
+    assert!(buf.is_empty());
+    assert_eq!(buf.drain_buffered(&mut out), 0);
+
+    //The remaining bytes are still in the Read impl.
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"he stream");
+}
+
+#[test]
+pub fn test_read_frame() {
+    let large = (0..100u8).collect::<Vec<u8>>();
+    let mut data = Vec::new();
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data.extend_from_slice(&5u32.to_be_bytes());
+    data.extend_from_slice(b"hello");
+    data.extend_from_slice(&100u32.to_be_bytes());
+    data.extend_from_slice(&large);
+
+    let mut reader = MaxChunkReader(&data, 7);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut frame = b"x".to_vec();
+    assert_eq!(
+        buf.read_frame(&mut reader, 100, &mut frame).unwrap(),
+        Some(0)
+    );
+    assert_eq!(frame, b"x");
+    frame.clear();
+    assert_eq!(
+        buf.read_frame(&mut reader, 100, &mut frame).unwrap(),
+        Some(5)
+    );
+    assert_eq!(frame, b"hello");
+    frame.clear();
+    //The frame is larger than the internal buffer.
+    assert_eq!(
+        buf.read_frame(&mut reader, 100, &mut frame).unwrap(),
+        Some(100)
+    );
+    assert_eq!(frame, large);
+    assert_eq!(buf.read_frame(&mut reader, 100, &mut frame).unwrap(), None);
+
+    //Oversized frames are rejected before the payload is read.
+    let mut cursor = Cursor::new(&data[13..]);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut frame = Vec::new();
+    assert_eq!(
+        buf.read_frame(&mut cursor, 99, &mut frame)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidData
+    );
+    assert!(frame.is_empty());
+    assert_eq!(buf.read_u32_be(&mut cursor).unwrap(), 100);
+
+    //Truncated payload.
+    let mut cursor = Cursor::new(&data[4..12]);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        buf.read_frame(&mut cursor, 100, &mut frame)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert!(frame.is_empty());
+
+    //Truncated length prefix.
+    let mut cursor = Cursor::new(&data[..2]);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    assert_eq!(
+        buf.read_frame(&mut cursor, 100, &mut frame)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+pub fn test_budgeted_resume_after_would_block() {
+    let mut reader = AlternatingErrorReader(b"0123456789", 3, ErrorKind::WouldBlock, false);
+    let mut buf = UnownedReadBuffer::<16>::new();
+    let mut target = [0u8; 8];
+    let mut count = 0usize;
+    loop {
+        match buf
+            .read_exact_budgeted(&mut reader, &mut target[count..], 10)
+            .unwrap()
+        {
+            Budgeted::Complete => break,
+            Budgeted::Exhausted(progress) => count += progress,
+        }
+    }
+    assert_eq!(&target, b"01234567");
+    assert_eq!(buf.available(), 1);
+
+    let data = b"0123456789abcdefghijklmnop";
+    let mut target = WouldBlockWriter(Vec::new(), vec![8]);
+    let mut buf = UnownedWriteBuffer::<16>::new();
+    assert_eq!(
+        buf.write_all_budgeted(&mut target, data, 10).unwrap(),
+        Budgeted::Exhausted(24)
+    );
+    assert_eq!(target.0, b"01234567");
+    target.1 = vec![100, 100];
+    assert_eq!(
+        buf.write_all_budgeted(&mut target, &data[24..], 10)
+            .unwrap(),
+        Budgeted::Complete
+    );
+    buf.flush(&mut target.0).unwrap();
+    assert_eq!(target.0, data);
+}
+
+#[test]
+pub fn test_borrowed_types_are_generic_over_the_size() {
+    let mut read_buf = UnownedReadBuffer::<16>::new();
+    let mut reader = Cursor::new(b"ab".to_vec());
+    let mut borrowed: BorrowedReadBuffer<'_, Cursor<Vec<u8>>, 16> = read_buf.borrow(&mut reader);
+    let chars: Chars<'_, Cursor<Vec<u8>>, 16> = borrowed.chars();
+    assert_eq!(chars.map(Result::unwrap).collect::<String>(), "ab");
+
+    let mut write_buf = UnownedWriteBuffer::<16>::new();
+    let mut sink = Vec::new();
+    let mut borrowed: BorrowedWriteBuffer<'_, Vec<u8>, 16> = write_buf.borrow(&mut sink);
+    borrowed.write_all(b"ab").unwrap();
+    borrowed.flush().unwrap();
+    assert_eq!(sink, b"ab");
+
+    let mut read_buf = DynReadBuffer::with_capacity(32);
+    let mut reader = Cursor::new(b"cd".to_vec());
+    let mut borrowed: BorrowedDynReadBuffer<'_, Cursor<Vec<u8>>> = read_buf.borrow(&mut reader);
+    let mut text = String::new();
+    borrowed.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "cd");
+
+    let mut write_buf = DynWriteBuffer::with_capacity(32);
+    let mut sink = Vec::new();
+    let mut borrowed: BorrowedDynWriteBuffer<'_, Vec<u8>> = write_buf.borrow(&mut sink);
+    borrowed.write_all(b"cd").unwrap();
+    borrowed.flush().unwrap();
+    assert_eq!(sink, b"cd");
+}